@@ -0,0 +1,84 @@
+use crate::{grid::Grid, point::point::Point2d};
+
+// no step of a sweep is longer than this along either axis, so a move can
+// never hop over a cell however fast the unit is
+const MAX_STEP: f64 = 0.5;
+
+/// The points a move from `from` to `to` passes through, split into equal
+/// steps short enough that no cell in between is skipped. Ends at `to` and
+/// leaves out `from` itself.
+pub fn sweep(from: Point2d<f64>, to: Point2d<f64>) -> impl Iterator<Item = Point2d<f64>> {
+    let offset = to - from;
+    let steps = (offset.x.abs().max(offset.y.abs()) / MAX_STEP).ceil().max(1.0) as u32;
+    (1..=steps).map(move |step| from + offset * (f64::from(step) / f64::from(steps)))
+}
+
+/// Moves a unit from `from` to `to` without entering a wall cell, checking
+/// every cell crossed on the way rather than only the last one. When a step
+/// is blocked the unit slides along whichever axis is still free for the rest
+/// of the move, and only stops when both are blocked. Units already stuck
+/// inside a wall may move freely so they can get out.
+pub fn resolve(grid: &Grid, from: Point2d<f64>, to: Point2d<f64>) -> Point2d<f64> {
+    let free = |x: f64, y: f64| {
+        grid.cell_at(Point2d::new(x, y))
+            .is_some_and(|cell| !grid.is_blocked(cell))
+    };
+    let (mut x, mut y) = (from.x, from.y);
+    let (mut move_x, mut move_y) = (true, true);
+    for step in sweep(from, to) {
+        let next_x = if move_x { step.x } else { x };
+        let next_y = if move_y { step.y } else { y };
+        // slipping diagonally between two walls that touch at the corner
+        // would be another way through
+        let squeezed = !free(next_x, y) && !free(x, next_y);
+        if !free(x, y) || (free(next_x, next_y) && !squeezed) {
+            (x, y) = (next_x, next_y);
+        } else if free(next_x, y) {
+            x = next_x;
+            move_y = false;
+        } else if free(x, next_y) {
+            y = next_y;
+            move_x = false;
+        } else {
+            break;
+        }
+    }
+    Point2d::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_moves_stop_at_thin_walls() {
+        let mut grid = Grid::new(20, 20);
+        for y in 0..20 {
+            grid.block(Point2d::new(10, y));
+        }
+        for to in [Point2d::new(15.0, 5.0), Point2d::new(19.0, 19.0), Point2d::new(10.6, 5.0)] {
+            let end = resolve(&grid, Point2d::new(5.0, 5.0), to);
+            assert!(end.x.round() < 10.0, "tunnelled to {:?}", end);
+        }
+    }
+
+    #[test]
+    fn diagonal_moves_do_not_squeeze_between_touching_corners() {
+        let mut grid = Grid::new(10, 10);
+        grid.block(Point2d::new(5, 4));
+        grid.block(Point2d::new(4, 5));
+        let end = resolve(&grid, Point2d::new(4.0, 4.0), Point2d::new(6.0, 6.0));
+        assert_eq!(grid.cell_at(end), Some(Point2d::new(4, 4)));
+    }
+
+    #[test]
+    fn blocked_moves_slide_along_the_wall() {
+        let mut grid = Grid::new(10, 10);
+        for x in 0..10 {
+            grid.block(Point2d::new(x, 6));
+        }
+        let end = resolve(&grid, Point2d::new(2.0, 4.0), Point2d::new(6.0, 8.0));
+        assert_eq!(end.x, 6.0);
+        assert!(end.y.round() < 6.0);
+    }
+}
@@ -0,0 +1,569 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+use crossterm::terminal;
+
+use crate::{
+    game::{CellWidth, GameBuilder, Mode},
+    generator::{Cave, Maze, RoomsAndCorridors, SymmetricArena},
+    input::KeyMap,
+    net,
+    replay::Replay,
+    ui,
+    unit::{parse_behaviour, Enemy, Movement, Steering},
+};
+
+pub const HELP: &str = "\
+Usage: application [options]
+
+Every option can also be set in a config file, one `<option> <value>` per
+line without the leading dashes. Later options override earlier ones, so
+flags after `--config` override the file. Lines starting with `#` are
+comments.
+
+Options:
+  -h, --help                  Print this help and exit
+      --config <file>         Read options from a file
+      --width <cells>         Arena width, 20 to 1000, or `terminal` to fill
+                              the terminal [default: 80]
+      --height <cells>        Arena height, 10 to 1000, or `terminal` to fill
+                              the terminal below the HUD [default: 40]
+      --cell-width <width>    Terminal columns per arena cell, narrow for one
+                              or wide for two with emoji [default: narrow]
+      --seed <number>         Seed for a reproducible run [default: random]
+      --map <file>            Play an arena drawn in a text file, replaces
+                              the size, arena, walls and enemies
+      --arena <kind>          scatter, cave, rooms, maze or symmetric
+                              [default: scatter]
+      --walls <count>         Walls the scatter arena places [default: 30]
+      --difficulty <level>    easy, normal or hard, scales enemy speed and
+                              player health [default: normal]
+      --tick <millis>         Milliseconds per tick, 1 to 10000 [default: 70]
+      --enemies <list>        Enemies separated by `;`, each a speed and an
+                              optional behaviour: chase, flee, wander,
+                              ambush <lookahead> or
+                              patrol <next> <x,y> <x,y>...
+                              [default: 11 chasers, 0.05 to 0.55]
+      --health <points>       Player starting health, 1 to 255 [default: 10]
+      --speed <cells>         Player starting speed per tick, 0 to 1
+                              [default: 0.5]
+      --mode <mode>           single, coop or versus [default: single]
+      --steering <steering>   steps:<count> or free:<degrees> [default: steps:8]
+      --movement <movement>   direct, inertia or
+                              inertia:<acceleration>,<friction>,<max speed>
+                              [default: direct]
+      --knockback <cells>     How far hits push the player [default: 0]
+      --reload <ticks>        Ticks between shots [default: 3]
+      --kill-score <points>   Points per enemy shot [default: 1]
+      --invulnerability <ticks>
+                              Ticks the player cannot be hurt after a hit
+                              [default: 10]
+      --keys <file>           Key bindings, one `bind <key> <action> [<player>]`,
+                              `unbind <key>` or `clear` per line
+      --save <file>           Where the save key writes [default: savegame.txt]
+      --load <file>           Continue a saved game
+      --record <file>         Write a replay of the run
+      --replay <file>         Play back a recorded run
+      --host <address>        Host a two-player game on `[host:]port`
+      --join <address>        Join a hosted game on `host[:port]`
+";
+
+const DEFAULT_WIDTH: u16 = 80;
+const DEFAULT_HEIGHT: u16 = 40;
+const DEFAULT_WALLS: u16 = 30;
+const MIN_SIZE: (u16, u16) = (20, 10);
+const MAX_SIZE: u16 = 1000;
+
+/// How the interior of a generated arena is laid out.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Arena {
+    Scatter,
+    Cave,
+    Rooms,
+    Maze,
+    Symmetric,
+}
+
+impl Arena {
+    const ALL: [Arena; 5] = [Arena::Scatter, Arena::Cave, Arena::Rooms, Arena::Maze, Arena::Symmetric];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Arena::Scatter => "scatter",
+            Arena::Cave => "cave",
+            Arena::Rooms => "rooms",
+            Arena::Maze => "maze",
+            Arena::Symmetric => "symmetric",
+        }
+    }
+}
+
+impl Display for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Arena {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Arena::ALL
+            .into_iter()
+            .find(|arena| arena.name() == s)
+            .ok_or_else(|| format!("unknown arena `{}`, expected scatter, cave, rooms, maze or symmetric", s))
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// What every enemy's speed is multiplied by.
+    pub fn enemy_speed_scale(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+        }
+    }
+
+    /// The starting health for a player that would start with `health`.
+    pub fn health(&self, health: u8) -> u8 {
+        match self {
+            Difficulty::Easy => health.saturating_add(health / 2),
+            Difficulty::Normal => health,
+            Difficulty::Hard => (health / 2).max(1),
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name() == s)
+            .ok_or_else(|| format!("unknown difficulty `{}`, expected easy, normal or hard", s))
+    }
+}
+
+/// Everything the binary can be told on the command line or in a config
+/// file, see `HELP`. Options left unset fall back to the defaults listed
+/// there.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub help: bool,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    // `terminal` was given for the width or height, see `fit_terminal`
+    pub fit_width: bool,
+    pub fit_height: bool,
+    pub cell_width: Option<CellWidth>,
+    pub seed: Option<u64>,
+    pub map: Option<PathBuf>,
+    pub arena: Option<Arena>,
+    pub walls: Option<u16>,
+    pub difficulty: Option<Difficulty>,
+    pub tick: Option<Duration>,
+    // speeds and behaviours, checked by `parse_behaviour` when set
+    pub enemies: Option<Vec<(f64, String)>>,
+    pub health: Option<u8>,
+    pub speed: Option<f64>,
+    pub mode: Option<Mode>,
+    pub steering: Option<Steering>,
+    pub movement: Option<Movement>,
+    pub knockback: Option<f64>,
+    pub reload: Option<u16>,
+    pub kill_score: Option<u32>,
+    pub invulnerability: Option<u16>,
+    pub keys: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub load: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub host: Option<String>,
+    pub join: Option<String>,
+}
+
+impl Config {
+    /// Reads the options from command line arguments, without the program
+    /// name. `--name value` and `--name=value` are both accepted.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-h" || arg == "--help" {
+                config.help = true;
+                continue;
+            }
+            let Some(option) = arg.strip_prefix("--") else {
+                return Err(format!("unexpected argument `{}`", arg));
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None => (option, args.next().ok_or_else(|| format!("`--{}` needs a value", option))?),
+            };
+            if name == "config" {
+                config.read_file(&value)?;
+            } else {
+                config.set(name, &value).map_err(|e| format!("`--{}`: {}", name, e))?;
+            }
+        }
+        if !config.help {
+            config.fit_terminal()?;
+            config.validate()?;
+        }
+        Ok(config)
+    }
+
+    fn read_file(&mut self, path: &str) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read config {}: {}", path, e))?;
+        let lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty() && !text.starts_with('#')) {
+            let (name, value) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+            if name == "config" {
+                return Err(format!("{} line {}: config files cannot include other config files", path, line));
+            }
+            self.set(name, value.trim()).map_err(|e| format!("{} line {}: `{}`: {}", path, line, name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Sets the option called `name`, as written without the leading dashes.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "width" if value == "terminal" => (self.width, self.fit_width) = (None, true),
+            "height" if value == "terminal" => (self.height, self.fit_height) = (None, true),
+            "width" => (self.width, self.fit_width) = (Some(parse_in(value, MIN_SIZE.0..=MAX_SIZE)?), false),
+            "height" => (self.height, self.fit_height) = (Some(parse_in(value, MIN_SIZE.1..=MAX_SIZE)?), false),
+            "cell-width" => self.cell_width = Some(parse(value)?),
+            "seed" => self.seed = Some(parse(value)?),
+            "map" => self.map = Some(value.into()),
+            "arena" => self.arena = Some(parse(value)?),
+            "walls" => self.walls = Some(parse(value)?),
+            "difficulty" => self.difficulty = Some(parse(value)?),
+            "tick" => self.tick = Some(Duration::from_millis(parse_in(value, 1..=10_000)?)),
+            "enemies" => self.enemies = Some(parse_enemies(value)?),
+            "health" => self.health = Some(parse_in(value, 1..=u8::MAX)?),
+            "speed" => self.speed = Some(parse_in(value, 0.0..=1.0)?),
+            "mode" => self.mode = Some(parse(value)?),
+            "steering" => self.steering = Some(parse(value)?),
+            "movement" => self.movement = Some(parse(value)?),
+            "knockback" => self.knockback = Some(parse_in(value, 0.0..=f64::from(MAX_SIZE))?),
+            "reload" => self.reload = Some(parse(value)?),
+            "kill-score" => self.kill_score = Some(parse(value)?),
+            "invulnerability" => self.invulnerability = Some(parse(value)?),
+            "keys" => self.keys = Some(value.into()),
+            "save" => self.save = Some(value.into()),
+            "load" => self.load = Some(value.into()),
+            "record" => self.record = Some(value.into()),
+            "replay" => self.replay = Some(value.into()),
+            "host" => self.host = Some(net::with_default_port(value)),
+            "join" => self.join = Some(net::with_default_port(value)),
+            _ => return Err("unknown option, see --help".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Sizes the arena to fill the terminal where `terminal` was given for
+    /// the width or height. Done once every option is read, since the cell
+    /// width decides how many cells fit.
+    pub fn fit_terminal(&mut self) -> Result<(), String> {
+        if !self.fit_width && !self.fit_height {
+            return Ok(());
+        }
+        let (columns, rows) = terminal::size().map_err(|e| format!("cannot read the terminal size: {}", e))?;
+        let fit = |option: &str, cells: u16, min: u16| {
+            if cells < min {
+                return Err(format!(
+                    "`--{} terminal`: the terminal leaves room for {} cells, the arena needs at least {}",
+                    option, cells, min
+                ));
+            }
+            Ok(cells.min(MAX_SIZE))
+        };
+        if self.fit_width {
+            let cells = columns / self.cell_width.unwrap_or_default().columns();
+            self.width = Some(fit("width", cells, MIN_SIZE.0)?);
+        }
+        if self.fit_height {
+            self.height = Some(fit("height", rows.saturating_sub(ui::HUD_ROWS), MIN_SIZE.1)?);
+        }
+        (self.fit_width, self.fit_height) = (false, false);
+        Ok(())
+    }
+
+    /// Rejects options that cannot be used together.
+    pub fn validate(&self) -> Result<(), String> {
+        let conflict = |first: &str, second: &str| Err(format!("`--{}` cannot be combined with `--{}`", first, second));
+        if self.map.is_some() {
+            let set = [
+                ("width", self.width.is_some()),
+                ("height", self.height.is_some()),
+                ("arena", self.arena.is_some()),
+                ("walls", self.walls.is_some()),
+                ("enemies", self.enemies.is_some()),
+            ];
+            if let Some((option, _)) = set.into_iter().find(|(_, set)| *set) {
+                return conflict("map", option);
+            }
+        }
+        if self.replay.is_some() && self.load.is_some() {
+            return conflict("replay", "load");
+        }
+        // a save and a replay's starting state bring all of these along, only
+        // the key map and the cell width apply to a loaded or replayed game
+        let starts = [("load", self.load.is_some()), ("replay", self.replay.is_some())];
+        if let Some((start, _)) = starts.into_iter().find(|(_, set)| *set) {
+            let set = [
+                ("width", self.width.is_some()),
+                ("height", self.height.is_some()),
+                ("seed", self.seed.is_some()),
+                ("map", self.map.is_some()),
+                ("mode", self.mode.is_some()),
+                ("arena", self.arena.is_some()),
+                ("walls", self.walls.is_some()),
+                ("enemies", self.enemies.is_some()),
+                ("tick", self.tick.is_some()),
+                ("difficulty", self.difficulty.is_some()),
+                ("health", self.health.is_some()),
+                ("speed", self.speed.is_some()),
+                ("steering", self.steering.is_some()),
+                ("movement", self.movement.is_some()),
+                ("knockback", self.knockback.is_some()),
+                ("reload", self.reload.is_some()),
+                ("kill-score", self.kill_score.is_some()),
+                ("invulnerability", self.invulnerability.is_some()),
+                ("save", self.save.is_some()),
+            ];
+            if let Some((option, _)) = set.into_iter().find(|(_, set)| *set) {
+                return conflict(start, option);
+            }
+        }
+        if self.walls.is_some() && self.arena.is_some_and(|arena| arena != Arena::Scatter) {
+            return Err("`--walls` only applies to the scatter arena".to_string());
+        }
+        if let Some(walls) = self.walls {
+            let (width, height) = self.size();
+            let interior = u32::from(width - 2) * u32::from(height - 2);
+            if u32::from(walls) > interior / 2 {
+                return Err(format!(
+                    "`--walls`: {} walls do not fit a {}x{} arena, at most {} do",
+                    walls,
+                    width,
+                    height,
+                    interior / 2
+                ));
+            }
+        }
+        if self.host.is_some() && self.join.is_some() {
+            return conflict("host", "join");
+        }
+        if self.host.is_some() && self.mode == Some(Mode::Single) {
+            return Err("`--host` needs a two-player mode, coop or versus".to_string());
+        }
+        Ok(())
+    }
+
+    /// The arena size, ignoring any map.
+    pub fn size(&self) -> (u16, u16) {
+        (
+            self.width.unwrap_or(DEFAULT_WIDTH),
+            self.height.unwrap_or(DEFAULT_HEIGHT),
+        )
+    }
+
+    /// Sets up a game as configured, reading the map and replay files.
+    pub fn game_builder(&self) -> Result<GameBuilder, String> {
+        let mut builder = match &self.map {
+            Some(path) => GameBuilder::from_map_file(path)
+                .map_err(|e| format!("failed to load map {}: {}", path.display(), e))?,
+            None => {
+                let (width, height) = self.size();
+                let builder = GameBuilder::new().width(width).height(height);
+                let builder = match self.arena.unwrap_or(Arena::Scatter) {
+                    Arena::Scatter => builder.n_random_walls(self.walls.unwrap_or(DEFAULT_WALLS)),
+                    Arena::Cave => builder.generator(Cave::default()),
+                    Arena::Rooms => builder.generator(RoomsAndCorridors::default()),
+                    Arena::Maze => builder.generator(Maze::default()),
+                    Arena::Symmetric => builder.generator(SymmetricArena::default()),
+                };
+                let enemies = match &self.enemies {
+                    Some(enemies) => enemies
+                        .iter()
+                        .filter_map(|(speed, behaviour)| {
+                            parse_behaviour(behaviour).map(|behaviour| Enemy::with_behaviour(*speed, behaviour))
+                        })
+                        .collect(),
+                    None => (1..12).map(|i| Enemy::with_speed(i as f64 * 0.05)).collect(),
+                };
+                builder.enemies(enemies)
+            }
+        };
+        let difficulty = self.difficulty.unwrap_or_default();
+        builder = builder
+            .update_interval(self.tick.unwrap_or(Duration::from_millis(70)))
+            .player_starting_health(difficulty.health(self.health.unwrap_or(10)))
+            .player_starting_speed(self.speed.unwrap_or(0.5))
+            .enemy_speed_scale(difficulty.enemy_speed_scale());
+        // hosting needs a second player, co-op unless asked otherwise
+        if let Some(mode) = self.mode.or(self.host.as_ref().map(|_| Mode::Coop)) {
+            builder = builder.mode(mode);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        if let Some(steering) = self.steering {
+            builder = builder.player_steering(steering);
+        }
+        if let Some(movement) = self.movement {
+            builder = builder.player_movement(movement);
+        }
+        if let Some(knockback) = self.knockback {
+            builder = builder.knockback(knockback);
+        }
+        if let Some(reload) = self.reload {
+            builder = builder.reload_ticks(reload);
+        }
+        if let Some(kill_score) = self.kill_score {
+            builder = builder.kill_score(kill_score);
+        }
+        if let Some(invulnerability) = self.invulnerability {
+            builder = builder.player_invulnerability(invulnerability);
+        }
+        if let Some(path) = &self.save {
+            builder = builder.save_path(path);
+        }
+        if let Some(path) = &self.record {
+            builder = builder.record_replay(path);
+        }
+        if let Some(path) = &self.replay {
+            let replay = Replay::load(path).map_err(|e| format!("failed to load replay {}: {}", path.display(), e))?;
+            builder = builder.replay(replay);
+        }
+        if let Some(cell_width) = self.cell_width {
+            builder = builder.cell_width(cell_width);
+        }
+        Ok(builder.key_map(self.key_map()?))
+    }
+
+    /// The configured key bindings, the defaults without `--keys`.
+    pub fn key_map(&self) -> Result<KeyMap, String> {
+        match &self.keys {
+            Some(path) => KeyMap::load(path).map_err(|e| format!("failed to load key map {}: {}", path.display(), e)),
+            None => Ok(KeyMap::default()),
+        }
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value.parse().map_err(|e| format!("invalid value `{}`: {}", value, e))
+}
+
+fn parse_in<T: FromStr + PartialOrd + Display>(value: &str, range: std::ops::RangeInclusive<T>) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let parsed = parse(value)?;
+    if !range.contains(&parsed) {
+        return Err(format!("{} is out of range, expected {} to {}", value, range.start(), range.end()));
+    }
+    Ok(parsed)
+}
+
+// `<speed> [<behaviour>]` separated by `;`, `none` for no enemies
+fn parse_enemies(value: &str) -> Result<Vec<(f64, String)>, String> {
+    if value.trim() == "none" {
+        return Ok(vec![]);
+    }
+    value
+        .split(';')
+        .map(|enemy| {
+            let enemy = enemy.trim();
+            let (speed, behaviour) = enemy.split_once(char::is_whitespace).unwrap_or((enemy, "chase"));
+            let speed = parse_in(speed, 0.0..=f64::from(MAX_SIZE))?;
+            let behaviour = behaviour.trim();
+            if parse_behaviour(behaviour).is_none() {
+                return Err(format!("invalid enemy behaviour `{}`", behaviour));
+            }
+            Ok((speed, behaviour.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        Config::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn loading_refuses_the_options_it_would_ignore() {
+        for option in ["--width=30", "--seed=3", "--arena=cave", "--difficulty=normal", "--save=other.txt"] {
+            let name = option[2..].split('=').next().unwrap();
+            assert_eq!(
+                parse(&["--load=save.txt", option]).unwrap_err(),
+                format!("`--load` cannot be combined with `--{}`", name)
+            );
+        }
+        assert!(parse(&["--load=save.txt", "--keys=keys.txt", "--cell-width=wide", "--record=run.txt"]).is_ok());
+    }
+
+    #[test]
+    fn replaying_refuses_the_options_it_would_ignore() {
+        let options = [
+            "--width=30",
+            "--height=20",
+            "--seed=3",
+            "--arena=cave",
+            "--walls=5",
+            "--enemies=2",
+            "--mode=coop",
+            "--difficulty=normal",
+            "--health=5",
+            "--save=other.txt",
+            "--load=save.txt",
+        ];
+        for option in options {
+            let name = option[2..].split('=').next().unwrap();
+            assert_eq!(
+                parse(&["--replay=run.txt", option]).unwrap_err(),
+                format!("`--replay` cannot be combined with `--{}`", name)
+            );
+        }
+        assert!(parse(&["--replay=run.txt", "--keys=keys.txt", "--cell-width=wide", "--record=copy.txt"]).is_ok());
+    }
+}
@@ -1,46 +1,185 @@
 #![allow(unused_imports)]
 
+mod campaign;
+mod error;
+mod save;
+
+pub use campaign::{Campaign, Level};
+pub use error::GameError;
+pub use crate::ui::CellWidth;
+
 use std::{
-    io::{stdout, Stdout, Write},
+    collections::VecDeque,
+    io,
+    path::PathBuf,
     time::{Duration, Instant},
 };
 
-use rand::rngs::ThreadRng;
+use crossterm::event::Event;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
 use crate::{
+    collision,
+    generator::{self, Generator, Scatter},
     hud::Hud,
-    input,
+    grid::Grid,
+    input::{self, Action, KeyMap},
+    map::{Map, MapError},
+    point::point::Point2d,
+    pathfinding::FlowField,
+    replay::Replay,
     traits::*,
-    ui::{draw::*, UI},
-    unit::Collectible,
+    ui::{self, draw::*, frame::Frame, UI},
+    unit::{Collectible, CollectibleKind, Effect},
+    unit::Context,
+    unit::Projectile,
     unit::Enemy,
     unit::Wall,
-    unit::{Player, PlayerBuilder},
+    unit::{Movement, Player, PlayerBuilder, Steering},
 };
 
+const SPAWN_STREAM: u64 = 0x5EED_5EED_5EED_5EED;
+const PROJECTILE_SPEED: f64 = 1.0;
+const POWER_UP_HEALTH: u8 = 3;
+const MAGNET_RADIUS: i32 = 8;
+// where the first player starts unless told otherwise, if the arena is big enough
+const DEFAULT_SPAWN: Point2d<u16> = Point2d { x: 30, y: 15 };
+// what tells the players apart
+pub(crate) const WEAPONS: [char; 2] = ['🔫', '🏹'];
+
+enum Stream {
+    Map,
+    Spawn,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Status {
+    Running,
+    GameOver,
+    Won,
+    Quit,
+    /// The next level of a campaign could not be built, `Game::run` returns why.
+    Failed,
+}
+
+/// How many players share the keyboard and what they play for.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Mode {
+    Single,
+    /// Two players against the enemies, the run is over once both are dead.
+    Coop,
+    /// Two players who can also shoot each other, the last one standing wins.
+    Versus,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Single, Mode::Coop, Mode::Versus];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Single => "single",
+            Mode::Coop => "coop",
+            Mode::Versus => "versus",
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        match self {
+            Mode::Single => 1,
+            Mode::Coop | Mode::Versus => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| format!("unknown mode `{}`", s))
+    }
+}
+
 pub struct Game {
     height: u16,
     width: u16,
-    stdout: Stdout,
-    score: u32,
+    tick: u64,
+    // one score per player, indexed like `players`
+    scores: Vec<u32>,
     enemies: Vec<Enemy>,
-    n_random_walls: u16,
+    knockback: f64,
+    kill_score: u32,
+    reload_ticks: u16,
+    projectiles: Vec<Projectile>,
     walls: Vec<Wall>,
+    grid: Grid,
+    flow_field: Option<FlowField>,
     collectible: Collectible,
-    player: Player,
-    ui: UI,
-    rng: ThreadRng,
+    collectible_spots: Vec<Point2d<u16>>,
+    power_ups: Vec<Collectible>,
+    power_up_interval: u16,
+    max_power_ups: usize,
+    power_up_ticks: u16,
+    randomize_enemies: bool,
+    mode: Mode,
+    players: Vec<Player>,
+    seed: u64,
+    // map generation and runtime spawns draw from separate streams so that
+    // changing one never shifts the other
+    map_rng: ChaCha8Rng,
+    spawn_rng: ChaCha8Rng,
+    recording: Replay,
+    record_path: Option<PathBuf>,
+    playback: Option<Replay>,
+    save_path: PathBuf,
+    key_map: KeyMap,
+    cell_width: CellWidth,
+    // campaign progress, `level` stays 0 outside of a campaign
+    level: usize,
+    level_count: usize,
+    level_start_score: u32,
+    target_score: Option<u32>,
+    levels: VecDeque<Level>,
+    // why the next level could not be built, which ends the run
+    level_failure: Option<GameError>,
     update_interval_millis: Duration,
 }
 
 pub struct GameBuilder {
     height: u16,
     width: u16,
-    n_random_walls: u16,
+    seed: Option<u64>,
+    record_path: Option<PathBuf>,
+    playback: Option<Replay>,
+    save_path: PathBuf,
+    key_map: KeyMap,
+    cell_width: CellWidth,
+    generator: Option<Box<dyn Generator>>,
+    knockback: f64,
+    kill_score: u32,
+    reload_ticks: u16,
     update_interval: Duration,
+    mode: Mode,
+    spawn: Option<Point2d<u16>>,
     player_builder: PlayerBuilder,
     enemies: Vec<Enemy>,
+    randomize_enemies: bool,
+    enemy_speed_scale: f64,
     walls: Vec<Wall>,
+    collectible_spots: Vec<Point2d<u16>>,
+    power_up_interval: u16,
+    max_power_ups: usize,
+    power_up_ticks: u16,
 }
 
 impl GameBuilder {
@@ -49,18 +188,69 @@ impl GameBuilder {
         Self {
             height: 48,
             width: 80,
+            seed: None,
+            record_path: None,
+            playback: None,
+            save_path: PathBuf::from("savegame.txt"),
+            key_map: KeyMap::default(),
+            cell_width: CellWidth::default(),
+            spawn: None,
             player_builder: PlayerBuilder::new(),
-            n_random_walls: 0,
+            generator: None,
+            knockback: 0.0,
+            kill_score: 1,
+            reload_ticks: 3,
             update_interval: Duration::from_millis(50),
+            mode: Mode::Single,
             enemies: vec![
                 Enemy::with_speed(0.6),
                 Enemy::with_speed(0.5),
                 Enemy::with_speed(0.4),
             ],
+            randomize_enemies: true,
+            enemy_speed_scale: 1.0,
             walls: vec![],
+            collectible_spots: vec![],
+            power_up_interval: 200,
+            max_power_ups: 2,
+            power_up_ticks: 150,
         }
     }
 
+    /// Starts from an arena described by a text grid, see `Map` for the format.
+    /// The arena size, walls, player spawn, enemies and collectible spots all
+    /// come from the map.
+    pub fn from_map(map: &str) -> Result<Self, MapError> {
+        Ok(Self::new().map(map.parse()?))
+    }
+
+    pub fn from_map_file(path: impl AsRef<std::path::Path>) -> Result<Self, MapError> {
+        Ok(Self::new().map(Map::load(path)?))
+    }
+
+    fn map(mut self, map: Map) -> Self {
+        self.width = map.width;
+        self.height = map.height;
+        self.walls = map
+            .walls
+            .into_iter()
+            .map(|wall| Wall::new(wall.x, wall.y))
+            .collect();
+        self.spawn = Some(map.player);
+        self.enemies = map
+            .enemies
+            .into_iter()
+            .map(|(position, speed)| {
+                let mut enemy = Enemy::with_speed(speed);
+                enemy.set_position(Point2d::new(position.x.into(), position.y.into()));
+                enemy
+            })
+            .collect();
+        self.randomize_enemies = false;
+        self.collectible_spots = map.collectible_spots;
+        self
+    }
+
     pub fn width(mut self, width: u16) -> Self {
         self.width = width;
         self
@@ -71,6 +261,54 @@ impl GameBuilder {
         self
     }
 
+    /// Fixes the seed of every random placement, so the same seed and
+    /// settings always yield the same run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Writes every action of the run to `path` once `Game::run` returns.
+    pub fn record_replay(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_path = Some(path.into());
+        self
+    }
+
+    /// Feeds the recorded actions into `Game::run` instead of the keyboard.
+    /// The replay's starting state replaces the arena and every setting of
+    /// the run. Replays recorded during a campaign need the campaign and are
+    /// played back with `Campaign::replay`.
+    pub fn replay(mut self, replay: Replay) -> Self {
+        self.seed = Some(replay.seed());
+        self.playback = Some(replay);
+        self
+    }
+
+    /// Where the save hotkey writes the game to.
+    pub fn save_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.save_path = path.into();
+        self
+    }
+
+    /// Which keys control the players in `Game::run`.
+    pub fn key_map(mut self, key_map: KeyMap) -> Self {
+        self.key_map = key_map;
+        self
+    }
+
+    /// How many terminal columns `Game::draw` gives each cell.
+    pub fn cell_width(mut self, cell_width: CellWidth) -> Self {
+        self.cell_width = cell_width;
+        self
+    }
+
+    /// Plays with two players on one keyboard. The second player starts
+    /// opposite the first, with the same health and speed.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     pub fn player_starting_health(mut self, health: u8) -> Self {
         self.player_builder = self.player_builder.health(health);
         self
@@ -81,8 +319,61 @@ impl GameBuilder {
         self
     }
 
+    /// Where the first player starts, 30,15 by default or the middle of
+    /// arenas too small for that. Must be inside the outer wall and not on a
+    /// given wall, generated walls move the player out of the way instead.
+    pub fn player_position(mut self, x: u16, y: u16) -> Self {
+        self.spawn = Some(Point2d::new(x, y));
+        self
+    }
+
+    /// How finely the players can aim, eight headings by default.
+    pub fn player_steering(mut self, steering: Steering) -> Self {
+        self.player_builder = self.player_builder.steering(steering);
+        self
+    }
+
+    /// How the players' speed moves them, `Movement::Direct` by default.
+    pub fn player_movement(mut self, movement: Movement) -> Self {
+        self.player_builder = self.player_builder.movement(movement);
+        self
+    }
+
+    /// How many ticks the player cannot be hurt again after taking a hit.
+    pub fn player_invulnerability(mut self, ticks: u16) -> Self {
+        self.player_builder = self.player_builder.invulnerability(ticks);
+        self
+    }
+
+    /// How far a hit pushes the player away from the enemy, 0 disables knockback.
+    pub fn knockback(mut self, distance: f64) -> Self {
+        self.knockback = distance;
+        self
+    }
+
+    /// Points awarded for each enemy shot down.
+    pub fn kill_score(mut self, score: u32) -> Self {
+        self.kill_score = score;
+        self
+    }
+
+    /// Ticks the player has to wait between shots.
+    pub fn reload_ticks(mut self, ticks: u16) -> Self {
+        self.reload_ticks = ticks;
+        self
+    }
+
+    /// Shorthand for scattering single walls with `generator::Scatter`.
     pub fn n_random_walls(mut self, n_random_walls: u16) -> Self {
-        self.n_random_walls = n_random_walls;
+        self.generator = Some(Box::new(Scatter::new(n_random_walls)));
+        self
+    }
+
+    /// Lays out the interior with `generator`, on top of any walls given.
+    /// Cells the player cannot reach are walled off afterwards so that
+    /// everything spawns in one connected region.
+    pub fn generator(mut self, generator: impl Generator + 'static) -> Self {
+        self.generator = Some(Box::new(generator));
         self
     }
 
@@ -93,6 +384,13 @@ impl GameBuilder {
 
     pub fn enemies(mut self, enemies: Vec<Enemy>) -> Self {
         self.enemies = enemies;
+        self.randomize_enemies = true;
+        self
+    }
+
+    /// Multiplies the speed of every enemy, including those from a map.
+    pub fn enemy_speed_scale(mut self, scale: f64) -> Self {
+        self.enemy_speed_scale = scale;
         self
     }
 
@@ -101,36 +399,307 @@ impl GameBuilder {
         self
     }
 
-    pub fn build(self) -> Game {
-        Game {
+    /// A power-up drops every `interval` ticks while fewer than `max` are on
+    /// the field, and its effect lasts `duration` ticks. An interval of 0
+    /// disables power-ups.
+    pub fn power_ups(mut self, interval: u16, max: usize, duration: u16) -> Self {
+        self.power_up_interval = interval;
+        self.max_power_ups = max;
+        self.power_up_ticks = duration;
+        self
+    }
+
+    /// Restricts where the collectible may appear, any free cell is used when empty.
+    pub fn collectible_spots(mut self, spots: Vec<Point2d<u16>>) -> Self {
+        self.collectible_spots = spots;
+        self
+    }
+
+    /// Where the first player starts, after checking that the arena has room
+    /// for them there, that the collectible can reach every spot set for it
+    /// and that nothing placed among the given walls is sealed off from it.
+    fn spawn(&self) -> Result<Point2d<u16>, GameError> {
+        let (width, height) = (self.width, self.height);
+        if width < 3 || height < 3 {
+            return Err(GameError::TooSmall { width, height });
+        }
+        let inside = |cell: Point2d<u16>| (1..width - 1).contains(&cell.x) && (1..height - 1).contains(&cell.y);
+        let spawn = match self.spawn {
+            Some(spawn) if !inside(spawn) => return Err(GameError::SpawnOutside { spawn, width, height }),
+            Some(spawn) => spawn,
+            None if inside(DEFAULT_SPAWN) => DEFAULT_SPAWN,
+            None => Point2d::new(width / 2, height / 2),
+        };
+        if self.walls.iter().any(|wall| wall.position() == spawn) {
+            return Err(GameError::SpawnInWall { spawn });
+        }
+        for &spot in &self.collectible_spots {
+            if !inside(spot) {
+                return Err(GameError::SpotOutside { spot, width, height });
+            }
+            if self.walls.iter().any(|wall| wall.position() == spot) {
+                return Err(GameError::SpotInWall { spot });
+            }
+        }
+        // generated arenas are connected up once generated, pockets left by
+        // the given walls are only walled up when there is nothing in them
+        if self.generator.is_none() {
+            let mut grid = Grid::from_walls(width, height, &self.walls);
+            let border: Vec<_> = grid.cells().filter(|&cell| !inside(cell)).collect();
+            border.into_iter().for_each(|cell| grid.block(cell));
+            let reachable = grid.flood_fill(spawn);
+            let reachable = |cell: Point2d<u16>| reachable[grid.index(cell)];
+            if let Some(&spot) = self.collectible_spots.iter().find(|&&spot| !reachable(spot)) {
+                return Err(GameError::SpotUnreachable { spot });
+            }
+            // randomly placed enemies only land where the player can go
+            if !self.randomize_enemies {
+                for enemy in self.enemies.iter().map(|enemy| enemy.position().round().to_u16()) {
+                    if !grid.contains(enemy) || !reachable(enemy) {
+                        return Err(GameError::EnemyUnreachable { enemy });
+                    }
+                }
+            }
+        }
+        Ok(spawn)
+    }
+
+    /// Fails if the arena is too small, the player spawn or a collectible
+    /// spot is outside it or on a wall, a collectible spot or an enemy from a
+    /// map is walled off from the spawn, or walls leave no free cell at all.
+    pub fn build(mut self) -> Result<Game, GameError> {
+        if let Some(start) = self.playback.as_ref().and_then(Replay::start) {
+            let mut game = Game::parse_save(start).map_err(GameError::InvalidReplay)?;
+            // the levels after the recorded one are not in the replay
+            if game.level > 0 {
+                return Err(GameError::InvalidReplay(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "replay was recorded during a campaign, play it back with `Campaign::replay`",
+                )));
+            }
+            game.record_path = self.record_path;
+            game.save_path = self.save_path;
+            game.key_map = self.key_map;
+            game.cell_width = self.cell_width;
+            game.playback = self.playback;
+            return Ok(game);
+        }
+        let spawn = self.spawn()?;
+        self.player_builder = self.player_builder.position(spawn.x.into(), spawn.y.into());
+
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        for enemy in &mut self.enemies {
+            enemy.set_speed(enemy.speed() * self.enemy_speed_scale);
+        }
+        let mut game = Game {
             height: self.height,
             width: self.width,
-            n_random_walls: self.n_random_walls,
+            knockback: self.knockback,
+            kill_score: self.kill_score,
+            reload_ticks: self.reload_ticks,
+            projectiles: vec![],
             update_interval_millis: self.update_interval,
             enemies: self.enemies,
             walls: self.walls,
+            grid: Grid::new(self.width, self.height),
+            flow_field: None,
             collectible: Collectible::default(),
-            player: self.player_builder.build(),
-            ui: UI::new(),
-            rng: rand::thread_rng(),
-            stdout: stdout(),
-            score: 0,
-        }
+            collectible_spots: self.collectible_spots,
+            power_ups: vec![],
+            power_up_interval: self.power_up_interval,
+            max_power_ups: self.max_power_ups,
+            power_up_ticks: self.power_up_ticks,
+            randomize_enemies: self.randomize_enemies,
+            mode: self.mode,
+            players: (0..self.mode.players())
+                .map(|index| self.player_builder.clone().weapon(WEAPONS[index]).build())
+                .collect(),
+            seed,
+            map_rng: ChaCha8Rng::seed_from_u64(seed),
+            spawn_rng: ChaCha8Rng::seed_from_u64(seed ^ SPAWN_STREAM),
+            recording: Replay::new(seed),
+            record_path: self.record_path,
+            playback: self.playback,
+            save_path: self.save_path,
+            key_map: self.key_map,
+            cell_width: self.cell_width,
+            level: 0,
+            level_count: 0,
+            level_start_score: 0,
+            target_score: None,
+            levels: VecDeque::new(),
+            level_failure: None,
+            tick: 0,
+            scores: vec![0; self.mode.players()],
+        };
+        game.init(self.generator)?;
+        Ok(game)
     }
 }
 
 impl Game {
     pub fn new() -> Self {
-        Self::builder().build()
+        Self::default()
     }
 
     pub fn builder() -> GameBuilder {
         GameBuilder::new()
     }
 
-    pub fn init(&mut self) {
-        self.ui.prepare();
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Every action applied so far, ready to be saved and played back.
+    pub fn recording(&self) -> &Replay {
+        &self.recording
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// The combined score of every player.
+    pub fn score(&self) -> u32 {
+        self.scores.iter().sum()
+    }
+
+    pub fn scores(&self) -> &[u32] {
+        &self.scores
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn key_map(&self) -> &KeyMap {
+        &self.key_map
+    }
+
+    /// The key map can be changed while the game runs, the next key press
+    /// already uses the new bindings.
+    pub fn key_map_mut(&mut self) -> &mut KeyMap {
+        &mut self.key_map
+    }
+
+    pub fn cell_width(&self) -> CellWidth {
+        self.cell_width
+    }
+
+    /// The cell width is not saved, loaded games are drawn narrow until
+    /// told otherwise.
+    pub fn set_cell_width(&mut self, cell_width: CellWidth) {
+        self.cell_width = cell_width;
+    }
+
+    /// Writes every action from here on to `path` once `Game::run` returns,
+    /// like `GameBuilder::record_replay` for a loaded game.
+    pub fn record_replay(&mut self, path: impl Into<PathBuf>) {
+        self.record_path = Some(path.into());
+    }
+
+    /// The first player.
+    pub fn player(&self) -> &Player {
+        &self.players[0]
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    pub fn walls(&self) -> &[Wall] {
+        &self.walls
+    }
+
+    pub fn projectiles(&self) -> &[Projectile] {
+        &self.projectiles
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn collectible(&self) -> &Collectible {
+        &self.collectible
+    }
+
+    pub fn power_ups(&self) -> &[Collectible] {
+        &self.power_ups
+    }
+
+    pub fn status(&self) -> Status {
+        let alive = self.players.iter().filter(|player| player.is_alive()).count();
+        if self.level_failure.is_some() {
+            Status::Failed
+        } else if alive == 0 {
+            Status::GameOver
+        } else if (self.mode == Mode::Versus && alive < self.players.len())
+            || (self.level_complete() && self.level >= self.level_count)
+        {
+            // versus is also won by outliving the other player
+            Status::Won
+        } else {
+            Status::Running
+        }
+    }
+
+    /// Who won a versus run: the last player standing, or the higher score
+    /// when the campaign is won with both alive. `None` on a draw, while the
+    /// run is still going and in the other modes.
+    pub fn winner(&self) -> Option<usize> {
+        if self.mode != Mode::Versus || self.status() != Status::Won {
+            return None;
+        }
+        let alive: Vec<_> = (0..self.players.len())
+            .filter(|&index| self.players[index].is_alive())
+            .collect();
+        if let [winner] = alive[..] {
+            return Some(winner);
+        }
+        let best = self.scores.iter().max()?;
+        let leaders: Vec<_> = (0..self.scores.len())
+            .filter(|&index| self.scores[index] == *best)
+            .collect();
+        match leaders[..] {
+            [winner] => Some(winner),
+            _ => None,
+        }
+    }
+
+    fn cheats(&self) -> Vec<(bool, bool, bool)> {
+        self.players
+            .iter()
+            .map(|player| (player.noclip(), player.speed_limit(), player.invincibility()))
+            .collect()
+    }
+
+    fn set_cheats(&mut self, cheats: Vec<(bool, bool, bool)>) {
+        for (player, (noclip, speed_limit, invincibility)) in self.players.iter_mut().zip(cheats) {
+            if player.noclip() != noclip {
+                player.toggle_noclip();
+            }
+            if player.speed_limit() != speed_limit {
+                player.toggle_speed();
+            }
+            if player.invincibility() != invincibility {
+                player.toggle_invincibility();
+            }
+        }
+    }
 
+    fn init(&mut self, generator: Option<Box<dyn Generator>>) -> Result<(), GameError> {
         // surround the game area with walls
         for x in 0..self.width {
             self.walls.push(Wall::new(x, 0));
@@ -141,123 +710,796 @@ impl Game {
             self.walls.push(Wall::new(self.width - 1, y));
         }
 
-        // add random walls
-        for _ in 0..self.n_random_walls {
-            let mut wall = Wall::default();
-            wall.set_rand_position(&mut self.rng, 1..self.width - 1, 1..self.height - 1);
-            self.walls.push(wall);
+        self.grid = Grid::from_walls(self.width, self.height, &self.walls);
+
+        // generate the interior, then wall off whatever the player cannot reach
+        if let Some(generator) = generator {
+            generator.generate(&mut self.grid, &mut self.map_rng);
+        }
+        let spawn = self.grid.cell_at(self.players[0].position()).unwrap_or_default();
+        let connected_spawn = generator::connect(&mut self.grid, spawn).ok_or(GameError::ArenaFull)?;
+        if connected_spawn != spawn {
+            self.players[0].set_position(Point2d::new(connected_spawn.x.into(), connected_spawn.y.into()));
         }
+        let grid = &self.grid;
+        self.collectible_spots.retain(|&spot| !grid.is_blocked(spot));
+        self.walls = self
+            .grid
+            .blocked_cells()
+            .map(|cell| Wall::new(cell.x, cell.y))
+            .collect();
 
-        // randomize enemy positions
-        self.enemies.iter_mut().for_each(|enemy| {
-            enemy.set_rand_position(
-                &mut self.rng,
-                1.0..(self.width - 1).into(),
-                1.0..(self.height - 1).into(),
-            );
-        });
+        // the second player starts mirrored across the arena, facing the other way
+        let first = self.players[0].position();
+        let heading = self.players[0].heading() + 180.0;
+        let mirrored = Point2d::new(
+            (f64::from(self.width - 1) - first.x).clamp(0.0, f64::from(self.width - 1)),
+            (f64::from(self.height - 1) - first.y).clamp(0.0, f64::from(self.height - 1)),
+        );
+        let spawn = self.grid.cell_at(mirrored).and_then(|cell| self.grid.nearest_free(cell));
+        if let (Some(second), Some(spawn)) = (self.players.get_mut(1), spawn) {
+            second.set_position(Point2d::new(spawn.x.into(), spawn.y.into()));
+            second.set_heading(heading);
+        }
+
+        // randomize enemy positions, keeping them out of walls
+        if self.randomize_enemies {
+            let grid = &self.grid;
+            self.enemies.iter_mut().for_each(|enemy| loop {
+                enemy.set_rand_position(
+                    &mut self.map_rng,
+                    1.0..(self.width - 1).into(),
+                    1.0..(self.height - 1).into(),
+                );
+                if grid.cell_at(enemy.position()).is_some_and(|cell| !grid.is_blocked(cell)) {
+                    break;
+                }
+            });
+        }
 
         // randomize collectible position
-        while self
-            .walls
-            .iter()
-            .any(|wall| wall.position() == self.collectible.position())
-        {
-            self.collectible.set_rand_position(
-                &mut self.rng,
-                1..self.width - 1,
-                1..self.height - 1,
+        let spot = self.random_spot(Stream::Map);
+        self.collectible.set_position(spot);
+        Ok(())
+    }
+
+    /// A free cell for a pickup, one of the collectible spots if there are any.
+    fn random_spot(&mut self, stream: Stream) -> Point2d<u16> {
+        let rng = match stream {
+            Stream::Map => &mut self.map_rng,
+            Stream::Spawn => &mut self.spawn_rng,
+        };
+        if !self.collectible_spots.is_empty() {
+            return self.collectible_spots[rng.gen_range(0..self.collectible_spots.len())];
+        }
+        let mut spot = Collectible::default();
+        loop {
+            spot.set_rand_position(rng, 1..self.width - 1, 1..self.height - 1);
+            if !self.grid.is_blocked(spot.position()) {
+                return spot.position();
+            }
+        }
+    }
+
+    fn add_score(&mut self, index: usize, points: u32) {
+        if self.players[index].has_effect(Effect::ScoreMultiplier) {
+            self.scores[index] += points * 2;
+        } else {
+            self.scores[index] += points;
+        }
+    }
+
+    fn pick_up(&mut self, index: usize, kind: CollectibleKind) {
+        match kind {
+            CollectibleKind::Heart => self.add_score(index, 1),
+            CollectibleKind::Health => self.players[index].heal(POWER_UP_HEALTH),
+            _ => {}
+        }
+        if let Some(effect) = kind.effect() {
+            self.players[index].add_effect(effect, self.power_up_ticks);
+        }
+    }
+
+    // drag pickups near the player one cell closer every other tick
+    fn pull_pickups(&mut self, index: usize) {
+        let Some(player) = self.grid.cell_at(self.players[index].position()) else {
+            return;
+        };
+        if !self.players[index].has_effect(Effect::Magnet) || !self.tick.is_multiple_of(2) {
+            return;
+        }
+        let grid = &self.grid;
+        for pickup in self.power_ups.iter_mut().chain(std::iter::once(&mut self.collectible)) {
+            let position: Point2d<u16> = pickup.position();
+            let (dx, dy) = (player.x as i32 - position.x as i32, player.y as i32 - position.y as i32);
+            if dx.abs().max(dy.abs()) > MAGNET_RADIUS {
+                continue;
+            }
+            let next = Point2d::new(
+                (position.x as i32 + dx.signum()) as u16,
+                (position.y as i32 + dy.signum()) as u16,
             );
+            if !grid.is_blocked(next) {
+                pickup.set_position(next);
+            }
+        }
+    }
+
+    fn update_player(&mut self, index: usize) {
+        let player = &mut self.players[index];
+        player.recover();
+        player.drive();
+
+        // move player, sliding along walls unless noclip is on
+        let next_position = player.next_position();
+        if player.noclip() {
+            player.move_forward();
+        } else {
+            let start = player.position();
+            let position = collision::resolve(&self.grid, start, next_position);
+            player.set_position(position);
+            player.collide(position - start);
+        }
+
+        // pick up whatever the player is standing on
+        self.pull_pickups(index);
+        let cell = self.players[index].position().to_u16();
+        if cell == self.collectible.position() {
+            self.pick_up(index, CollectibleKind::Heart);
+            // move collectible to a new random position
+            let spot = self.random_spot(Stream::Spawn);
+            self.collectible.set_position(spot);
+        }
+        if let Some(power_up) = self.power_ups.iter().position(|power_up| power_up.position() == cell) {
+            let power_up = self.power_ups.swap_remove(power_up);
+            self.pick_up(index, power_up.kind());
         }
     }
 
     fn update(&mut self) {
-        // move player if not colliding with a wall
-        let player_next_position = self.player.forward_position();
-        if !self
-            .walls
-            .iter()
-            .any(|wall| wall.position() == player_next_position) ||
-            self.player.noclip()
+        for index in 0..self.players.len() {
+            if self.players[index].is_alive() {
+                self.update_player(index);
+            }
+        }
+
+        // drop a new power-up every so often
+        if self.power_up_interval > 0
+            && (self.tick + 1).is_multiple_of(self.power_up_interval as u64)
+            && self.power_ups.len() < self.max_power_ups
         {
-            self.player.move_forward();
+            let kind = CollectibleKind::POWER_UPS[self.spawn_rng.gen_range(0..CollectibleKind::POWER_UPS.len())];
+            let spot = self.random_spot(Stream::Spawn);
+            self.power_ups.push(Collectible::with_kind(spot.x, spot.y, kind));
         }
 
-        // increase score if player collides with collectible
-        if self.player.position().round().to_u16() == self.collectible.position() {
-            self.score += 1;
-            // move collectible to a new random position
-            self.collectible.set_rand_position(
-                &mut self.rng,
-                1..self.width - 1,
-                1..self.height - 1,
-            );
-            while self
-                .walls
-                .iter()
-                .any(|wall| wall.position() == self.collectible.position())
-            {
-                self.collectible.set_rand_position(
-                    &mut self.rng,
-                    1..self.width - 1,
-                    1..self.height - 1,
-                );
+        // move projectiles, each one stops at the first wall or enemy it hits,
+        // or in versus at the other player
+        let grid = &self.grid;
+        let enemies = &mut self.enemies;
+        let players = &mut self.players;
+        let versus = self.mode == Mode::Versus;
+        let mut kills = vec![];
+        self.projectiles.retain_mut(|projectile| {
+            let from = projectile.position();
+            projectile.move_forward();
+            // check every cell on the way so fast shots cannot pass through anything
+            for point in std::iter::once(from).chain(collision::sweep(from, projectile.position())) {
+                let Some(cell) = grid.cell_at(point).filter(|&cell| !grid.is_blocked(cell)) else {
+                    return false;
+                };
+                let hits = |position: Point2d<f64>| grid.cell_at(position) == Some(cell);
+                if let Some(enemy) = enemies.iter_mut().find(|enemy| enemy.is_alive() && hits(enemy.position())) {
+                    enemy.take_damage(projectile.damage());
+                    if !enemy.is_alive() {
+                        kills.push(projectile.owner());
+                    }
+                    return false;
+                }
+                let target = players.iter_mut().enumerate().find(|(index, player)| {
+                    versus && *index != projectile.owner() && player.is_alive() && hits(player.position())
+                });
+                if let Some((_, player)) = target {
+                    player.take_damage(projectile.damage());
+                    return false;
+                }
             }
+            true
+        });
+        self.enemies.retain(Enemy::is_alive);
+        for owner in kills {
+            self.add_score(owner, self.kill_score);
         }
 
-        // move enemies
+        // let every enemy act on its behaviour, sharing one path to the nearest living player
+        let targets: Vec<_> = self
+            .players
+            .iter()
+            .filter(|player| player.is_alive())
+            .filter_map(|player| self.grid.cell_at(player.position()))
+            .collect();
+        if self.flow_field.as_ref().map(FlowField::targets) != Some(&targets[..]) {
+            self.flow_field = (!targets.is_empty()).then(|| FlowField::with_targets(&self.grid, targets));
+        }
+        let mut context = Context {
+            grid: &self.grid,
+            flow_field: self.flow_field.as_ref(),
+            players: &self.players,
+            rng: &mut self.spawn_rng,
+        };
         self.enemies
             .iter_mut()
-            .for_each(|enemy| enemy.move_towards_player(&self.player));
+            .for_each(|enemy| enemy.update(&mut context));
 
-        // reduce player health for each enemy collision
-        self.enemies.iter_mut().for_each(|enemy| {
-            if enemy.position().round() == self.player.position().round() {
-                self.player.take_damage(1);
+        // hurt every player an enemy catches, knocking them away from it
+        for index in 0..self.players.len() {
+            let position = self.players[index].position();
+            let attacker = self
+                .enemies
+                .iter()
+                .map(|enemy| enemy.position())
+                .find(|attacker| attacker.round() == position.round());
+            if let Some(attacker) = attacker {
+                if self.players[index].is_alive() && self.players[index].take_damage(1) && self.knockback > 0.0 {
+                    self.knock_back_player(index, attacker);
+                }
             }
-        });
+        }
     }
 
-    fn draw(&mut self) {
-        self.ui.clear();
-        let mut buffer: Vec<u8> = Vec::new();
-        self.walls.iter().for_each(|wall| wall.draw(&mut buffer));
-        self.player.draw(&mut buffer);
-        self.enemies
-            .iter()
-            .for_each(|enemy| enemy.draw(&mut buffer));
-        self.collectible.draw(&mut buffer);
-        Hud::Hud::new(self.score, &self.player, self.height + 2).draw(&mut buffer);
-        self.stdout
-            .write_all(&buffer)
-            .expect("failed to write to stdout");
-        self.stdout.flush().expect("Failed to flush stdout");
+    fn fire(&mut self, index: usize) {
+        let player = &mut self.players[index];
+        if player.is_alive() && player.fire(self.reload_ticks) {
+            self.projectiles
+                .push(Projectile::new(player.position(), player.direction(), PROJECTILE_SPEED, 1).with_owner(index));
+        }
+    }
+
+    fn knock_back_player(&mut self, index: usize, attacker: Point2d<f64>) {
+        let player = &mut self.players[index];
+        let position = player.position();
+        // caught head on, push straight back
+        let away = (position - attacker).normalize().unwrap_or(-player.direction());
+        let pushed = position + away * self.knockback;
+        let pushed = if player.noclip() {
+            pushed
+        } else {
+            collision::resolve(&self.grid, position, pushed)
+        };
+        player.set_position(pushed);
     }
 
-    pub fn run(&mut self) {
-        self.init();
+    /// Advances the simulation by one tick after applying `actions` in order.
+    /// This is the whole game minus the terminal, so it can be driven by tests,
+    /// bots or a server. Every action goes to the first player.
+    pub fn step(&mut self, actions: &[Action]) -> Status {
+        let actions: Vec<_> = actions.iter().map(|&action| (0, action)).collect();
+        self.step_players(&actions)
+    }
+
+    /// Like `step`, with every action paired with the index of the player it
+    /// is for. Actions for players that are not in the game are dropped.
+    pub fn step_players(&mut self, actions: &[(usize, Action)]) -> Status {
+        if self.status() != Status::Running {
+            return self.status();
+        }
+        // played back from here, the replay needs none of the settings that
+        // led up to it
+        if self.recording.start().is_none() {
+            self.recording.set_start(self.save_to_string());
+        }
         let mut quit = false;
-        while self.player.is_alive() && !quit {
-            // poll for key events for the duration of the update interval
-            let now = std::time::Instant::now();
+        for &(index, action) in actions {
+            if index >= self.players.len() {
+                continue;
+            }
+            if !action.is_external() {
+                self.recording.record(self.tick, index, action);
+            }
+            if action == Action::Fire {
+                self.fire(index);
+            }
+            input::handle_action(action, &mut self.players[index], &mut quit);
+        }
+        if quit {
+            return Status::Quit;
+        }
+        self.update();
+        self.tick += 1;
+        if self.level_complete() {
+            self.next_level();
+        }
+        self.status()
+    }
+
+    /// Whether the arena and its HUD fit on a terminal of `terminal`
+    /// columns and rows.
+    pub fn fits(&self, terminal: (u16, u16)) -> bool {
+        ui::centre(ui::screen_size(self.width, self.height, self.cell_width), terminal).is_some()
+    }
+
+    /// Draws the arena centred on `frame`, or a note asking for a bigger
+    /// terminal when it does not fit.
+    pub fn draw(&self, frame: &mut Frame) {
+        let size = ui::screen_size(self.width, self.height, self.cell_width);
+        let terminal = frame.size();
+        let Some(origin) = ui::centre(size, terminal) else {
+            return ui::draw_too_small(frame, size);
+        };
+        for wall in &self.walls {
+            wall.draw(frame, origin, self.cell_width);
+        }
+        for player in &self.players {
+            player.draw(frame, origin, self.cell_width);
+        }
+        for projectile in &self.projectiles {
+            projectile.draw(frame, origin, self.cell_width);
+        }
+        for enemy in &self.enemies {
+            enemy.draw(frame, origin, self.cell_width);
+        }
+        self.collectible.draw(frame, origin, self.cell_width);
+        for power_up in &self.power_ups {
+            power_up.draw(frame, origin, self.cell_width);
+        }
+        let mut hud = Hud::Hud::new(self.scores[0], &self.players[0], self.height + 2)
+            .tick_interval(self.update_interval_millis);
+        for (score, player) in self.scores.iter().zip(&self.players).skip(1) {
+            hud = hud.player(*score, player);
+        }
+        if self.level > 0 {
+            hud = hud.level(self.level, self.level_count);
+        }
+        // the HUD is usually wider than the arena, keep it on screen
+        let width = hud.footprint(self.cell_width);
+        hud.draw(frame, Point2d::new(origin.x.min(terminal.0.saturating_sub(width)), origin.y), self.cell_width);
+    }
+
+    /// Plays on this terminal until the game ends or is quit. Fails if the
+    /// terminal cannot be set up, read from or drawn to, after putting it
+    /// back the way it was.
+    pub fn run(&mut self) -> Result<(), GameError> {
+        let players = self.players.len();
+        self.run_with(players, |_, _| {})
+    }
+
+    /// The terminal loop behind `run`. Only the first `local_players` take
+    /// their input from the keyboard. `exchange` is handed the game and the
+    /// actions gathered for each tick before it is stepped, and may add
+    /// actions from elsewhere. It is called once more after the last tick,
+    /// when added actions are ignored.
+    pub(crate) fn run_with(
+        &mut self,
+        local_players: usize,
+        mut exchange: impl FnMut(&Game, &mut Vec<(usize, Action)>),
+    ) -> Result<(), GameError> {
+        let mut ui = UI::new();
+        let mut failures = Vec::new();
+        let played = ui
+            .prepare()
+            .and_then(|_| self.play(&mut ui, local_players, &mut exchange, &mut failures));
+        exchange(self, &mut Vec::new());
+        let restored = ui.restore();
+        // printing them while the game owned the terminal would garble it
+        for failure in &failures {
+            eprintln!("{}", failure);
+        }
+        let status = played?;
+        restored?;
+        if let Some(path) = &self.record_path {
+            if let Err(e) = self.recording.save(path) {
+                eprintln!("Failed to save replay to {}: {}", path.display(), e);
+            }
+        }
+        if let Some(failure) = self.level_failure.take() {
+            return Err(failure);
+        }
+        self.print_outcome(status);
+        Ok(())
+    }
+
+    fn play(
+        &mut self,
+        ui: &mut UI,
+        local_players: usize,
+        exchange: &mut impl FnMut(&Game, &mut Vec<(usize, Action)>),
+        failures: &mut Vec<String>,
+    ) -> io::Result<Status> {
+        let mut status = self.status();
+        while status == Status::Running {
+            // poll for events for the duration of the update interval
+            // external actions are never recorded, but a replay written by
+            // hand may still hold them
+            let mut actions = match &self.playback {
+                Some(replay) => replay
+                    .actions_at(self.tick)
+                    .into_iter()
+                    .filter(|(_, action)| !action.is_external())
+                    .collect(),
+                None => Vec::new(),
+            };
+            let now = Instant::now();
             while let Some(time_remaining) = self.update_interval_millis.checked_sub(now.elapsed())
             {
-                if let Some(key) = input::poll_key_event(time_remaining) {
-                    input::handle_key_event(key, &mut self.player, &mut quit);
+                let key = match input::poll_event(time_remaining)? {
+                    Some(Event::Key(key)) => key,
+                    Some(Event::Resize(columns, rows)) => {
+                        ui.resize(columns, rows);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if let Some((index, action)) = self.key_map.lookup(key, local_players) {
+                    // during playback the keyboard can only stop or suspend
+                    // the replay
+                    if self.playback.is_none() || matches!(action, Action::Quit | Action::Suspend) {
+                        actions.push((index, action));
+                    }
+                }
+            }
+            // being told to terminate ends the game like quitting does
+            if ui.handle_signals()? {
+                actions.push((0, Action::Quit));
+            }
+            exchange(self, &mut actions);
+
+            if actions.iter().any(|&(_, action)| action == Action::Suspend) {
+                ui.suspend()?;
+            }
+            if actions.iter().any(|&(_, action)| action == Action::Save) {
+                // a failed save should not end the run
+                if let Err(e) = self.save(&self.save_path) {
+                    failures.push(format!("Failed to save to {}: {}", self.save_path.display(), e));
+                }
+            }
+            if actions.iter().any(|&(_, action)| action == Action::ReloadKeys) {
+                // neither should a broken key map, the old bindings stay
+                if let Err(e) = self.key_map.reload() {
+                    failures.push(format!("Failed to reload the key map: {}", e));
                 }
             }
 
-            self.update();
-            self.draw();
+            // the game waits while the terminal is too small to show it
+            if !self.fits(ui.size()) {
+                actions.retain(|&(_, action)| action == Action::Quit);
+            }
+            if self.fits(ui.size()) || !actions.is_empty() {
+                status = self.step_players(&actions);
+            }
+            let mut frame = ui.frame();
+            self.draw(&mut frame);
+            ui.render(frame)?;
+        }
+        Ok(status)
+    }
+
+    pub(crate) fn print_outcome(&self, status: Status) {
+        match (status, self.mode, self.winner()) {
+            (Status::Won, Mode::Versus, Some(winner)) => print!("\nPlayer {} wins!", winner + 1),
+            (Status::Won, Mode::Versus, None) => print!("\nDraw!"),
+            (Status::Won, _, _) => print!("\nYou win!"),
+            _ => print!("\nGame over!"),
         }
-        self.ui.restore();
-        print!("\nGame over!");
-        println!("  Score: {}", self.score);
+        let scores: Vec<_> = self.scores.iter().map(u32::to_string).collect();
+        println!("  Score: {}  Seed: {}", scores.join(" / "), self.seed);
     }
 }
 
 impl Default for Game {
     fn default() -> Self {
-        Self::builder().build()
+        Self::builder()
+            .build()
+            .expect("the default arena always has room for the player")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_leave_out_external_actions() {
+        let mut game = GameBuilder::new().seed(1).build().unwrap();
+        game.step_players(&[(0, Action::Save), (0, Action::ReloadKeys), (0, Action::TurnLeft)]);
+        game.step_players(&[(0, Action::Suspend)]);
+        assert_eq!(game.recording().actions_at(0), vec![(0, Action::TurnLeft)]);
+        assert!(game.recording().actions_at(1).is_empty());
+    }
+
+    // drives, turns and shoots in a pattern that repeats every few ticks
+    fn actions(tick: u64) -> Vec<(usize, Action)> {
+        let pattern = [Action::Accelerate, Action::TurnLeft, Action::Fire, Action::TurnRight, Action::Fire];
+        vec![(0, pattern[tick as usize % pattern.len()])]
+    }
+
+    fn play_back(game: &mut Game) {
+        let replay = game.playback.take().unwrap();
+        while game.tick() <= replay.last_tick().unwrap() {
+            game.step_players(&replay.actions_at(game.tick()));
+        }
+    }
+
+    // walls up every cell, spawn included
+    struct Fill;
+    impl Generator for Fill {
+        fn generate(&self, grid: &mut Grid, _: &mut dyn rand::RngCore) {
+            let cells: Vec<_> = grid.cells().collect();
+            for cell in cells {
+                grid.block(cell);
+            }
+        }
+    }
+
+    #[test]
+    fn builders_refuse_arenas_that_cannot_be_played() {
+        let small = || GameBuilder::new().width(10).height(10);
+        assert!(matches!(small().width(2).build(), Err(GameError::TooSmall { width: 2, height: 10 })));
+        assert!(matches!(small().player_position(0, 4).build(), Err(GameError::SpawnOutside { .. })));
+        assert!(matches!(
+            small().player_position(9, 4).build(),
+            Err(GameError::SpawnOutside { width: 10, height: 10, .. })
+        ));
+        assert!(matches!(
+            small().player_position(4, 4).walls(vec![Wall::new(4, 4)]).build(),
+            Err(GameError::SpawnInWall { .. })
+        ));
+        assert!(matches!(small().generator(Fill).build(), Err(GameError::ArenaFull)));
+        assert!(matches!(Campaign::new().build(), Err(GameError::NoLevels)));
+    }
+
+    #[test]
+    fn builders_refuse_collectible_spots_that_cannot_be_reached() {
+        let small = || GameBuilder::new().width(10).height(10).player_position(1, 1);
+        let in_wall = small().walls(vec![Wall::new(5, 5)]).collectible_spots(vec![Point2d::new(5, 5)]);
+        assert!(matches!(in_wall.build(), Err(GameError::SpotInWall { .. })));
+        let outside = small().collectible_spots(vec![Point2d::new(3, 3), Point2d::new(3, 9)]);
+        assert!(matches!(outside.build(), Err(GameError::SpotOutside { .. })));
+        assert!(small().collectible_spots(vec![Point2d::new(8, 8)]).build().is_ok());
+        // campaigns check every level up front
+        let campaign = Campaign::new()
+            .level(small(), 5)
+            .level(small().collectible_spots(vec![Point2d::new(0, 0)]), 5);
+        assert!(matches!(campaign.build(), Err(GameError::SpotOutside { .. })));
+    }
+
+    #[test]
+    fn builders_refuse_pockets_with_something_in_them() {
+        let pocket = "\
+            ##########\n\
+            #P...#...#\n\
+            #....#.{}.#\n\
+            #....#...#\n\
+            ##########";
+        let spot = GameBuilder::from_map(&pocket.replace("{}", "*")).unwrap();
+        assert!(matches!(
+            spot.build(),
+            Err(GameError::SpotUnreachable { spot }) if spot == Point2d::new(7, 2)
+        ));
+        let enemy = GameBuilder::from_map(&pocket.replace("{}", "5")).unwrap();
+        assert!(matches!(
+            enemy.build(),
+            Err(GameError::EnemyUnreachable { enemy }) if enemy == Point2d::new(7, 2)
+        ));
+        // an empty pocket is walled up, so nothing can appear in it later
+        let game = GameBuilder::from_map(&pocket.replace("{}", ".")).unwrap().build().unwrap();
+        assert!((6..9).all(|x| (1..4).all(|y| game.grid.is_blocked(Point2d::new(x, y)))));
+        // walls given to the builder are checked the same way
+        let walls = (0..10).map(|y| Wall::new(5, y)).collect();
+        let walled = GameBuilder::new().width(10).height(10).player_position(2, 2).walls(walls);
+        let walled = walled.collectible_spots(vec![Point2d::new(7, 7)]);
+        assert!(matches!(walled.build(), Err(GameError::SpotUnreachable { .. })));
+    }
+
+    #[test]
+    fn steps_run_the_game_without_a_terminal() {
+        let mut game = GameBuilder::new().seed(1).build().unwrap();
+        let start = game.player().position();
+        for _ in 0..5 {
+            assert_eq!(game.step(&[Action::Accelerate]), Status::Running);
+        }
+        assert_eq!(game.tick(), 5);
+        assert_ne!(game.player().position(), start);
+        assert_eq!(game.step(&[Action::Quit]), Status::Quit);
+        assert_eq!(game.tick(), 5);
+    }
+
+    #[test]
+    fn the_same_seed_and_actions_give_the_same_run() {
+        let build = || {
+            GameBuilder::new()
+                .seed(7)
+                .generator(crate::generator::RoomsAndCorridors::default())
+                .build()
+                .unwrap()
+        };
+        let (mut first, mut second) = (build(), build());
+        assert_eq!(first.save_to_string(), second.save_to_string());
+        for tick in 0..200 {
+            assert_eq!(first.step_players(&actions(tick)), second.step_players(&actions(tick)));
+        }
+        assert_eq!(first.save_to_string(), second.save_to_string());
+        assert_ne!(first.save_to_string(), GameBuilder::new().seed(8).build().unwrap().save_to_string());
+    }
+
+    #[test]
+    fn loaded_games_continue_like_the_saved_one() {
+        let mut game = GameBuilder::new().seed(5).mode(Mode::Coop).player_starting_health(100).build().unwrap();
+        for tick in 0..60 {
+            game.step_players(&actions(tick));
+        }
+        let mut loaded = Game::load_from_str(&game.save_to_string()).unwrap();
+        assert_eq!(loaded.save_to_string(), game.save_to_string());
+        for tick in 60..120 {
+            let mut actions = actions(tick);
+            actions.push((1, Action::Fire));
+            game.step_players(&actions);
+            loaded.step_players(&actions);
+        }
+        assert_eq!(loaded.save_to_string(), game.save_to_string());
+    }
+
+    #[test]
+    fn broken_saves_are_refused() {
+        let save = GameBuilder::new().seed(5).build().unwrap().save_to_string();
+        let broken = [
+            save.replacen("save 1", "save 2", 1),
+            save.replacen("tick 0", "tick x", 1),
+            save.replacen("tick 0", "tick 0 0", 1),
+            save.replacen("mode single", "mode coop", 1),
+            save.lines().filter(|line| !line.starts_with("rng")).collect::<Vec<_>>().join("\n"),
+        ];
+        for text in broken {
+            assert!(Game::load_from_str(&text).is_err(), "accepted:\n{}", text);
+        }
+    }
+
+    #[test]
+    fn replays_bring_the_arena_they_were_recorded_on() {
+        let mut game = GameBuilder::new()
+            .seed(3)
+            .width(40)
+            .height(20)
+            .generator(crate::generator::Cave::default())
+            .player_starting_health(100)
+            .build()
+            .unwrap();
+        for tick in 0..100 {
+            game.step_players(&actions(tick));
+        }
+        let replay = Replay::parse(&game.recording().to_string()).unwrap();
+        // none of the settings of the recorded run are given here
+        let mut played = GameBuilder::new().replay(replay).build().unwrap();
+        play_back(&mut played);
+        assert_eq!(played.save_to_string(), game.save_to_string());
+    }
+
+    #[test]
+    fn replays_recorded_after_loading_play_back() {
+        let mut game = GameBuilder::new().seed(4).player_starting_health(100).build().unwrap();
+        for tick in 0..50 {
+            game.step_players(&actions(tick));
+        }
+        let mut loaded = Game::load_from_str(&game.save_to_string()).unwrap();
+        for tick in 50..100 {
+            loaded.step_players(&actions(tick));
+        }
+        let replay = Replay::parse(&loaded.recording().to_string()).unwrap();
+        let mut played = GameBuilder::new().replay(replay).build().unwrap();
+        assert_eq!(played.tick(), 50);
+        play_back(&mut played);
+        assert_eq!(played.save_to_string(), loaded.save_to_string());
+    }
+
+    #[test]
+    fn knockback_stops_at_walls() {
+        let mut game = GameBuilder::new()
+            .width(20)
+            .height(10)
+            .enemies(vec![])
+            .player_position(5, 5)
+            .walls(vec![Wall::new(8, 5)])
+            .knockback(10.0)
+            .build()
+            .unwrap();
+        let start = game.players()[0].position();
+        game.knock_back_player(0, Point2d::new(start.x - 1.0, start.y));
+        let pushed = game.players()[0].position();
+        assert!(pushed.x > start.x && pushed.x < 7.5, "pushed to {:?}", pushed);
+        assert!(!game.grid.is_blocked(pushed.to_u16()));
+    }
+
+    // an arena with nothing in it but the player and the collectible,
+    // power-ups lasting 3 ticks and never dropping by themselves
+    fn empty_arena() -> Game {
+        let mut game = GameBuilder::new()
+            .width(30)
+            .height(15)
+            .n_random_walls(0)
+            .enemies(vec![])
+            .player_position(10, 7)
+            .player_starting_speed(0.5)
+            .power_ups(0, 0, 3)
+            .build()
+            .unwrap();
+        game.collectible.set_position(Point2d::new(20, 3));
+        game
+    }
+
+    #[test]
+    fn power_ups_work_until_they_wear_off() {
+        let mut game = empty_arena();
+        let speed = game.players[0].velocity().length();
+        game.pick_up(0, CollectibleKind::ScoreMultiplier);
+        game.pick_up(0, CollectibleKind::Shield);
+        game.pick_up(0, CollectibleKind::SpeedBoost);
+        game.pick_up(0, CollectibleKind::Heart);
+        game.add_score(0, 3);
+        assert_eq!(game.score(), 8);
+        assert!(!game.players[0].take_damage(1));
+        assert!(game.players[0].velocity().length() > speed);
+
+        for _ in 0..3 {
+            game.players[0].recover();
+        }
+        game.add_score(0, 3);
+        assert_eq!(game.score(), 11);
+        assert!(game.players[0].take_damage(1));
+        assert_eq!(game.players[0].velocity().length(), speed);
+    }
+
+    #[test]
+    fn magnets_pull_nearby_pickups_every_other_tick() {
+        let mut game = empty_arena();
+        let near = Point2d::new(13, 7);
+        game.collectible.set_position(near);
+        game.tick = 0;
+        game.pull_pickups(0);
+        assert_eq!(game.collectible.position(), near, "pulled without a magnet");
+
+        game.pick_up(0, CollectibleKind::Magnet);
+        game.pull_pickups(0);
+        assert_eq!(game.collectible.position(), Point2d::new(12, 7));
+        game.tick = 1;
+        game.pull_pickups(0);
+        assert_eq!(game.collectible.position(), Point2d::new(12, 7));
+
+        let far = Point2d::new(10 + MAGNET_RADIUS as u16 + 1, 7);
+        game.collectible.set_position(far);
+        game.tick = 2;
+        game.pull_pickups(0);
+        assert_eq!(game.collectible.position(), far);
+    }
+
+    #[test]
+    fn versus_is_won_by_the_last_player_standing() {
+        let mut game = GameBuilder::new().seed(1).mode(Mode::Versus).build().unwrap();
+        assert_eq!((game.status(), game.winner()), (Status::Running, None));
+        game.players[1].take_damage(u8::MAX);
+        assert_eq!((game.status(), game.winner()), (Status::Won, Some(0)));
+        game.players[0].take_damage(u8::MAX);
+        assert_eq!((game.status(), game.winner()), (Status::GameOver, None));
+    }
+
+    #[test]
+    fn coop_goes_on_until_both_players_are_dead() {
+        let mut game = GameBuilder::new().seed(1).mode(Mode::Coop).build().unwrap();
+        game.players[0].take_damage(u8::MAX);
+        assert_eq!((game.status(), game.winner()), (Status::Running, None));
+        game.players[1].take_damage(u8::MAX);
+        assert_eq!((game.status(), game.winner()), (Status::GameOver, None));
+    }
+
+    #[test]
+    fn versus_campaigns_won_with_both_alive_go_to_the_higher_score() {
+        // a target of 0 is met straight away, winning the only level
+        let mut game = Campaign::new()
+            .level(GameBuilder::new().seed(1).mode(Mode::Versus), 0)
+            .build()
+            .unwrap();
+        assert_eq!((game.status(), game.winner()), (Status::Won, None));
+        game.add_score(1, 2);
+        assert_eq!(game.winner(), Some(1));
+        game.add_score(0, 2);
+        assert_eq!(game.winner(), None);
     }
 }
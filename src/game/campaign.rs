@@ -0,0 +1,262 @@
+use std::{collections::VecDeque, fs, io, path::Path};
+
+use super::{Game, GameBuilder, GameError};
+use crate::replay::Replay;
+
+/// One stage of a campaign: the arena to play and how many points must be
+/// collected on it before moving on.
+pub struct Level {
+    builder: GameBuilder,
+    target_score: u32,
+}
+
+impl Level {
+    pub fn new(builder: GameBuilder, target_score: u32) -> Self {
+        Self {
+            builder,
+            target_score,
+        }
+    }
+}
+
+/// An ordered list of levels played as one run. Health and score carry over
+/// from one level to the next and the run is won once the last target is met.
+pub struct Campaign {
+    levels: VecDeque<Level>,
+}
+
+impl Campaign {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            levels: VecDeque::new(),
+        }
+    }
+
+    pub fn level(mut self, builder: GameBuilder, target_score: u32) -> Self {
+        self.levels.push_back(Level::new(builder, target_score));
+        self
+    }
+
+    pub fn levels(mut self, levels: Vec<Level>) -> Self {
+        self.levels.extend(levels);
+        self
+    }
+
+    /// Builds the first level with the remaining ones queued up behind it.
+    /// The queued levels are checked too, so that a broken one fails here
+    /// rather than halfway through the run.
+    pub fn build(mut self) -> Result<Game, GameError> {
+        for level in &self.levels {
+            level.builder.spawn()?;
+        }
+        let first = self.levels.pop_front().ok_or(GameError::NoLevels)?;
+        let mut game = first.builder.build()?;
+        game.level = 1;
+        game.level_count = self.levels.len() + 1;
+        game.target_score = Some(first.target_score);
+        game.levels = self.levels;
+        Ok(game)
+    }
+
+    /// Loads a save made during this campaign and queues up the levels that
+    /// come after the saved one.
+    pub fn load(self, path: impl AsRef<Path>) -> io::Result<Game> {
+        let path = path.as_ref();
+        let mut game = self.load_from_str(&fs::read_to_string(path)?)?;
+        game.save_path = path.to_path_buf();
+        Ok(game)
+    }
+
+    /// Like `load`, but reads the save from `text`.
+    pub fn load_from_str(mut self, text: &str) -> io::Result<Game> {
+        let mut game = Game::parse_save(text)?;
+        if game.level == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "save was not made during a campaign",
+            ));
+        }
+        if game.level_count != self.levels.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "save is from a campaign of {} levels but this one has {}",
+                    game.level_count,
+                    self.levels.len()
+                ),
+            ));
+        }
+        game.levels = self.levels.split_off(game.level);
+        Ok(game)
+    }
+
+    /// Feeds the actions of `replay`, recorded during this campaign, into
+    /// `Game::run` from the state it was recorded from, with the levels after
+    /// that one queued up again.
+    pub fn replay(self, replay: Replay) -> Result<Game, GameError> {
+        let start = replay.start().ok_or_else(|| {
+            GameError::InvalidReplay(io::Error::new(io::ErrorKind::InvalidData, "replay has no starting state"))
+        })?;
+        let mut game = self.load_from_str(start).map_err(GameError::InvalidReplay)?;
+        game.playback = Some(replay);
+        Ok(game)
+    }
+}
+
+impl Game {
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.level_count
+    }
+
+    pub(super) fn level_complete(&self) -> bool {
+        self.target_score
+            .is_some_and(|target| self.score() - self.level_start_score >= target)
+    }
+
+    /// Swaps in the arena of the next level, keeping the players' health,
+    /// cheats and scores. Does nothing once there are no levels left, and ends
+    /// the run with `Status::Failed` when the next level cannot be built.
+    pub(super) fn next_level(&mut self) {
+        let Some(Level {
+            mut builder,
+            target_score,
+        }) = self.levels.pop_front()
+        else {
+            return;
+        };
+        // derive unseeded levels from the current one so a seeded campaign stays reproducible
+        builder.seed.get_or_insert(self.seed.wrapping_add(1));
+        builder.mode = self.mode;
+        let mut next = match builder.build() {
+            Ok(next) => next,
+            // `Campaign::build` checked everything but generated walls, which
+            // can still fill up a level entirely
+            Err(error) => {
+                self.level_failure = Some(GameError::LevelFailed {
+                    level: self.level + 1,
+                    error: Box::new(error),
+                });
+                return;
+            }
+        };
+        for (player, previous) in next.players.iter_mut().zip(&self.players) {
+            player.set_health(previous.health(), previous.max_health());
+        }
+
+        let cheats = self.cheats();
+        self.height = next.height;
+        self.width = next.width;
+        self.enemies = next.enemies;
+        self.knockback = next.knockback;
+        self.kill_score = next.kill_score;
+        self.reload_ticks = next.reload_ticks;
+        self.projectiles = next.projectiles;
+        self.walls = next.walls;
+        self.grid = next.grid;
+        self.flow_field = None;
+        self.collectible = next.collectible;
+        self.collectible_spots = next.collectible_spots;
+        self.power_ups = next.power_ups;
+        self.power_up_interval = next.power_up_interval;
+        self.max_power_ups = next.max_power_ups;
+        self.power_up_ticks = next.power_up_ticks;
+        self.randomize_enemies = next.randomize_enemies;
+        self.players = next.players;
+        self.seed = next.seed;
+        self.map_rng = next.map_rng;
+        self.spawn_rng = next.spawn_rng;
+        self.update_interval_millis = next.update_interval_millis;
+        self.set_cheats(cheats);
+
+        self.level += 1;
+        self.level_start_score = self.score();
+        self.target_score = Some(target_score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Status, generator::Generator, grid::Grid, input::Action};
+
+    fn campaign() -> Campaign {
+        Campaign::new()
+            .level(GameBuilder::new().seed(1).width(20).height(10), 5)
+            .level(GameBuilder::new().seed(2).width(20).height(10), 5)
+            .level(GameBuilder::new().seed(3).width(20).height(10), 5)
+    }
+
+    #[test]
+    fn saves_keep_the_level_count() {
+        let game = campaign().build().unwrap();
+        let loaded = campaign().load_from_str(&game.save_to_string()).unwrap();
+        assert_eq!((loaded.level(), loaded.level_count()), (1, 3));
+        assert_eq!(loaded.levels.len(), 2);
+        assert_eq!(loaded.save_to_string(), game.save_to_string());
+    }
+
+    #[test]
+    fn campaign_saves_need_the_campaign() {
+        let text = campaign().build().unwrap().save_to_string();
+        assert!(Game::load_from_str(&text).is_err());
+        assert!(Campaign::new().level(GameBuilder::new(), 5).load_from_str(&text).is_err());
+    }
+
+    #[test]
+    fn campaign_replays_move_on_through_the_levels() {
+        // the first two targets are met on the first tick of their level
+        let campaign = || {
+            Campaign::new()
+                .level(GameBuilder::new().seed(1).width(20).height(10).player_starting_health(100), 0)
+                .level(GameBuilder::new().seed(2).width(30).height(12), 0)
+                .level(GameBuilder::new().seed(3).width(25).height(10), 1000)
+        };
+        let mut game = campaign().build().unwrap();
+        for tick in 0..30 {
+            let action = if tick % 3 == 0 { Action::TurnLeft } else { Action::Accelerate };
+            game.step(&[action]);
+        }
+        assert_eq!((game.level(), game.width()), (3, 25));
+        let recording = game.recording().to_string();
+
+        let replay = Replay::parse(&recording).unwrap();
+        assert!(matches!(GameBuilder::new().replay(replay).build(), Err(GameError::InvalidReplay(_))));
+        let mut played = campaign().replay(Replay::parse(&recording).unwrap()).unwrap();
+        let replay = played.playback.take().unwrap();
+        while played.tick() <= replay.last_tick().unwrap() {
+            played.step_players(&replay.actions_at(played.tick()));
+        }
+        assert_eq!(played.save_to_string(), game.save_to_string());
+    }
+
+    #[test]
+    fn levels_that_cannot_be_built_end_the_run() {
+        // walls up every cell, spawn included
+        struct Fill;
+        impl Generator for Fill {
+            fn generate(&self, grid: &mut Grid, _: &mut dyn rand::RngCore) {
+                let cells: Vec<_> = grid.cells().collect();
+                for cell in cells {
+                    grid.block(cell);
+                }
+            }
+        }
+
+        let mut game = Campaign::new()
+            .level(GameBuilder::new().seed(1).width(20).height(10), 0)
+            .level(GameBuilder::new().seed(2).width(20).height(10).generator(Fill), 5)
+            .build()
+            .unwrap();
+        assert_eq!(game.step(&[]), Status::Failed);
+        assert_eq!(game.level(), 1);
+        assert!(matches!(
+            game.level_failure,
+            Some(GameError::LevelFailed { level: 2, ref error }) if matches!(**error, GameError::ArenaFull)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+use crate::point::point::Point2d;
+
+/// Why a game could not be built or run.
+#[derive(Debug)]
+pub enum GameError {
+    /// The arena has no room inside its outer wall.
+    TooSmall { width: u16, height: u16 },
+    /// The player spawn is on or beyond the outer wall.
+    SpawnOutside { spawn: Point2d<u16>, width: u16, height: u16 },
+    /// The player spawn is on one of the walls given to the builder.
+    SpawnInWall { spawn: Point2d<u16> },
+    /// A collectible spot is on or beyond the outer wall.
+    SpotOutside { spot: Point2d<u16>, width: u16, height: u16 },
+    /// A collectible spot is on one of the walls given to the builder.
+    SpotInWall { spot: Point2d<u16> },
+    /// The walls given to the builder cut a collectible spot off from the spawn.
+    SpotUnreachable { spot: Point2d<u16> },
+    /// The walls given to the builder cut an enemy from a map off from the spawn.
+    EnemyUnreachable { enemy: Point2d<u16> },
+    /// Walls cover every cell, leaving nowhere to place anything.
+    ArenaFull,
+    /// A campaign was built without any levels.
+    NoLevels,
+    /// A campaign level could not be built once the run reached it.
+    LevelFailed { level: usize, error: Box<GameError> },
+    /// The starting state stored in a replay is not a valid save.
+    InvalidReplay(io::Error),
+    /// Talking to the terminal failed while running.
+    Io(io::Error),
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::TooSmall { width, height } => {
+                write!(f, "arena is {}x{}, it must be at least 3x3", width, height)
+            }
+            GameError::SpawnOutside { spawn, width, height } => write!(
+                f,
+                "player spawn {},{} is outside the {}x{} arena, it must be between 1,1 and {},{}",
+                spawn.x,
+                spawn.y,
+                width,
+                height,
+                width.saturating_sub(2),
+                height.saturating_sub(2)
+            ),
+            GameError::SpawnInWall { spawn } => write!(f, "player spawn {},{} is inside a wall", spawn.x, spawn.y),
+            GameError::SpotOutside { spot, width, height } => write!(
+                f,
+                "collectible spot {},{} is outside the {}x{} arena, it must be between 1,1 and {},{}",
+                spot.x,
+                spot.y,
+                width,
+                height,
+                width.saturating_sub(2),
+                height.saturating_sub(2)
+            ),
+            GameError::SpotInWall { spot } => write!(f, "collectible spot {},{} is inside a wall", spot.x, spot.y),
+            GameError::SpotUnreachable { spot } => {
+                write!(f, "collectible spot {},{} is walled off from the player spawn", spot.x, spot.y)
+            }
+            GameError::EnemyUnreachable { enemy } => {
+                write!(f, "enemy at {},{} is walled off from the player spawn", enemy.x, enemy.y)
+            }
+            GameError::ArenaFull => write!(f, "arena is full of walls, there is no free cell left"),
+            GameError::NoLevels => write!(f, "a campaign needs at least one level"),
+            GameError::LevelFailed { level, error } => write!(f, "level {} could not be built: {}", level, error),
+            GameError::InvalidReplay(e) => write!(f, "invalid replay: {}", e),
+            GameError::Io(e) => write!(f, "terminal error: {}", e),
+        }
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::InvalidReplay(e) | GameError::Io(e) => Some(e),
+            GameError::LevelFailed { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(e: io::Error) -> Self {
+        GameError::Io(e)
+    }
+}
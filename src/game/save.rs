@@ -0,0 +1,378 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    str::{FromStr, SplitWhitespace},
+};
+
+use super::{Game, GameBuilder, WEAPONS};
+use crate::{
+    grid::Grid,
+    point::point::{Point2d, Vec2},
+    traits::Position,
+    unit::{parse_behaviour, Collectible, Effect, Enemy, PlayerBuilder, Projectile, Wall},
+};
+
+// bump whenever a line is added, removed or changes meaning
+const HEADER: &str = "generic-rust-game save 1";
+
+impl Game {
+    /// Writes the full state of the run, including the position of both
+    /// random streams, so that a loaded game continues exactly as this one would.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.save_to_string())
+    }
+
+    /// Loads a save made outside of a campaign, saves made during one need
+    /// the rest of it and are loaded with `Campaign::load`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Game> {
+        let path = path.as_ref();
+        let mut game = Self::load_from_str(&fs::read_to_string(path)?)?;
+        game.save_path = path.to_path_buf();
+        Ok(game)
+    }
+
+    pub fn save_to_string(&self) -> String {
+        let mut text = String::new();
+        // writing to a String cannot fail
+        let _ = writeln!(text, "{}", HEADER);
+        let _ = writeln!(text, "seed {}", self.seed);
+        let _ = writeln!(text, "size {} {}", self.width, self.height);
+        let _ = writeln!(text, "interval {}", self.update_interval_millis.as_millis());
+        let _ = writeln!(text, "knockback {}", self.knockback);
+        let _ = writeln!(text, "weapon {} {}", self.reload_ticks, self.kill_score);
+        let _ = writeln!(text, "powerups {} {} {}", self.power_up_interval, self.max_power_ups, self.power_up_ticks);
+        let _ = writeln!(text, "mode {}", self.mode);
+        text.push_str(&self.state_to_string());
+        for spot in &self.collectible_spots {
+            let _ = writeln!(text, "spot {} {}", spot.x, spot.y);
+        }
+        for wall in &self.walls {
+            let position: Point2d<u16> = wall.position();
+            let _ = writeln!(text, "wall {} {}", position.x, position.y);
+        }
+        text
+    }
+
+    /// The lines of the save that change from one tick to the next, without
+    /// the header or anything about the arena itself.
+    pub(crate) fn state_to_string(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "rng {} {}", self.map_rng.get_word_pos(), self.spawn_rng.get_word_pos());
+        let _ = writeln!(text, "tick {}", self.tick);
+        let scores: Vec<_> = self.scores.iter().map(u32::to_string).collect();
+        let _ = writeln!(text, "score {}", scores.join(" "));
+        if let Some(target_score) = self.target_score {
+            let _ = writeln!(
+                text,
+                "level {} {} {} {}",
+                self.level, self.level_count, self.level_start_score, target_score
+            );
+        }
+        // effects belong to the player line above them
+        for player in &self.players {
+            let position = player.position();
+            let velocity = player.velocity();
+            let _ = writeln!(
+                text,
+                "player {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+                position.x,
+                position.y,
+                player.heading(),
+                player.steering(),
+                player.movement(),
+                velocity.x,
+                velocity.y,
+                player.speed(),
+                player.health(),
+                player.max_health(),
+                player.noclip(),
+                player.speed_limit(),
+                player.invincibility(),
+                player.invulnerability(),
+                player.invulnerable_ticks(),
+                player.reload_ticks(),
+            );
+            for (effect, ticks) in player.effects() {
+                let _ = writeln!(text, "effect {} {}", effect, ticks);
+            }
+        }
+        let collectible = self.collectible.position();
+        let _ = writeln!(text, "collectible {} {}", collectible.x, collectible.y);
+        for power_up in &self.power_ups {
+            let position = power_up.position();
+            let _ = writeln!(text, "powerup {} {} {}", position.x, position.y, power_up.kind());
+        }
+        for enemy in &self.enemies {
+            let position = enemy.position();
+            let _ = writeln!(
+                text,
+                "enemy {} {} {} {} {}",
+                position.x,
+                position.y,
+                enemy.speed(),
+                enemy.health(),
+                enemy.behaviour()
+            );
+        }
+        for projectile in &self.projectiles {
+            let position = projectile.position();
+            let _ = writeln!(
+                text,
+                "projectile {} {} {} {} {} {} {}",
+                position.x,
+                position.y,
+                projectile.direction().x,
+                projectile.direction().y,
+                projectile.speed(),
+                projectile.damage(),
+                projectile.owner(),
+            );
+        }
+        text
+    }
+
+    pub fn load_from_str(text: &str) -> io::Result<Game> {
+        let game = Self::parse_save(text)?;
+        if game.level > 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "save was made during a campaign, load it with `Campaign::load`",
+            ));
+        }
+        Ok(game)
+    }
+
+    /// Reads any save, including one made during a campaign. The levels
+    /// after the saved one are not part of it and are left for the caller to
+    /// queue up.
+    pub(crate) fn parse_save(text: &str) -> io::Result<Game> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            Some((_, other)) if other.starts_with("generic-rust-game save ") => {
+                return Err(invalid(1, format!("unsupported save version `{}`", other)))
+            }
+            _ => return Err(invalid(1, "not a save file".to_string())),
+        }
+
+        let mut builder = GameBuilder::new().enemies(vec![]);
+        let mut mode = None;
+        let mut state = State::default();
+        let mut spots = vec![];
+        let mut walls = vec![];
+
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+            let mut fields = Fields { line, words: text.split_whitespace() };
+            match fields.words.next() {
+                Some("seed") => builder = builder.seed(fields.next()?),
+                Some("size") => builder = builder.width(fields.next()?).height(fields.next()?),
+                Some("interval") => builder = builder.update_interval(std::time::Duration::from_millis(fields.next()?)),
+                Some("knockback") => builder = builder.knockback(fields.next()?),
+                Some("powerups") => builder = builder.power_ups(fields.next()?, fields.next()?, fields.next()?),
+                Some("weapon") => builder = builder.reload_ticks(fields.next()?).kill_score(fields.next()?),
+                Some("mode") => mode = Some(fields.next()?),
+                Some("spot") => spots.push(Point2d::new(fields.next()?, fields.next()?)),
+                Some("wall") => walls.push(Wall::new(fields.next()?, fields.next()?)),
+                Some(kind) if state.read(kind, &mut fields)? => {}
+                _ => return Err(invalid(line, format!("unexpected line `{}`", text))),
+            }
+            if fields.words.next().is_some() {
+                return Err(invalid(line, format!("trailing fields in `{}`", text)));
+            }
+        }
+
+        builder.mode = mode.ok_or_else(|| missing("mode"))?;
+        builder.generator = None;
+
+        let mut game = builder.build().map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        game.collectible_spots = spots;
+        game.grid = Grid::from_walls(game.width, game.height, &walls);
+        game.walls = walls;
+        state.apply(&mut game)?;
+        Ok(game)
+    }
+
+    /// Brings everything that moves up to date with `text`, written by
+    /// `state_to_string` on a game with the same arena, and keeps the arena.
+    pub(crate) fn load_state(&mut self, text: &str) -> io::Result<()> {
+        let mut state = State::default();
+        for (line, text) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            let mut fields = Fields { line, words: text.split_whitespace() };
+            match fields.words.next() {
+                Some(kind) if state.read(kind, &mut fields)? => {}
+                None => {}
+                _ => return Err(invalid(line, format!("unexpected line `{}`", text))),
+            }
+            if fields.words.next().is_some() {
+                return Err(invalid(line, format!("trailing fields in `{}`", text)));
+            }
+        }
+        state.apply(self)
+    }
+}
+
+// a player line before it becomes a player: the builder, velocity, cheats,
+// remaining invulnerable and reload ticks and the effects that follow it
+type PlayerLine = (PlayerBuilder, Vec2, (bool, bool, bool), u16, u16, Vec<(Effect, u16)>);
+
+/// The lines of a save that change from tick to tick, as read so far.
+#[derive(Default)]
+struct State {
+    rng: Option<(u128, u128)>,
+    tick: u64,
+    scores: Vec<u32>,
+    level: Option<(usize, usize, u32, u32)>,
+    players: Vec<PlayerLine>,
+    collectible: Collectible,
+    power_ups: Vec<Collectible>,
+    enemies: Vec<Enemy>,
+    projectiles: Vec<Projectile>,
+}
+
+impl State {
+    // reads the rest of a line starting with `kind`, false when it is not
+    // one of the state's lines
+    fn read(&mut self, kind: &str, fields: &mut Fields) -> io::Result<bool> {
+        let line = fields.line;
+        match kind {
+            "rng" => self.rng = Some((fields.next()?, fields.next()?)),
+            "tick" => self.tick = fields.next()?,
+            "score" => {
+                self.scores = fields.words.by_ref().map(|word| {
+                    word.parse().map_err(|_| invalid(line, format!("invalid field `{}`", word)))
+                }).collect::<io::Result<_>>()?
+            }
+            "level" => self.level = Some((fields.next()?, fields.next()?, fields.next()?, fields.next()?)),
+            "player" => {
+                let player_builder = PlayerBuilder::new()
+                    .position(fields.next()?, fields.next()?)
+                    .heading(fields.next()?)
+                    .steering(fields.next()?)
+                    .movement(fields.next()?);
+                let velocity = Vec2::new(fields.next()?, fields.next()?);
+                let player_builder = player_builder
+                    .speed(fields.next()?)
+                    .health(fields.next()?)
+                    .max_health(fields.next()?);
+                let flags = (fields.next()?, fields.next()?, fields.next()?);
+                let player_builder = player_builder.invulnerability(fields.next()?);
+                self.players.push((player_builder, velocity, flags, fields.next()?, fields.next()?, vec![]));
+            }
+            "effect" => {
+                let Some((.., effects)) = self.players.last_mut() else {
+                    return Err(invalid(line, "effect before any player".to_string()));
+                };
+                effects.push((fields.next::<Effect>()?, fields.next()?));
+            }
+            "powerup" => self.power_ups.push(Collectible::with_kind(fields.next()?, fields.next()?, fields.next()?)),
+            "collectible" => self.collectible = Collectible::new(fields.next()?, fields.next()?),
+            "enemy" => {
+                let position = Point2d::new(fields.next()?, fields.next()?);
+                let speed = fields.next()?;
+                let health = fields.next()?;
+                let behaviour = fields.words.by_ref().collect::<Vec<_>>().join(" ");
+                let behaviour = parse_behaviour(&behaviour)
+                    .ok_or_else(|| invalid(line, format!("invalid enemy behaviour `{}`", behaviour)))?;
+                let mut enemy = Enemy::with_behaviour(speed, behaviour).with_health(health);
+                enemy.set_position(position);
+                self.enemies.push(enemy);
+            }
+            "projectile" => {
+                let position = Point2d::new(fields.next()?, fields.next()?);
+                let direction = Vec2::new(fields.next()?, fields.next()?);
+                let projectile = Projectile::new(position, direction, fields.next()?, fields.next()?);
+                self.projectiles.push(projectile.with_owner(fields.next()?));
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn apply(self, game: &mut Game) -> io::Result<()> {
+        let mode = game.mode;
+        if self.players.len() != mode.players() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("save has {} players but {} needs {}", self.players.len(), mode, mode.players()),
+            ));
+        }
+        if self.scores.len() != self.players.len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("save has {} scores for {} players", self.scores.len(), self.players.len()),
+            ));
+        }
+        let (map_word_pos, spawn_word_pos) = self.rng.ok_or_else(|| missing("rng"))?;
+        if let Some((level, level_count, _, _)) = self.level {
+            if level == 0 || level > level_count {
+                return Err(invalid_level(level, level_count));
+            }
+        }
+
+        game.map_rng.set_word_pos(map_word_pos);
+        game.spawn_rng.set_word_pos(spawn_word_pos);
+        game.tick = self.tick;
+        game.scores = self.scores;
+        game.collectible = self.collectible;
+        game.power_ups = self.power_ups;
+        let mut cheats = vec![];
+        game.players = self
+            .players
+            .into_iter()
+            .enumerate()
+            .map(|(index, (player_builder, velocity, flags, invulnerable_ticks, reload_ticks, effects))| {
+                let mut player = player_builder.weapon(WEAPONS[index]).build();
+                player.set_velocity(velocity);
+                player.set_invulnerable_ticks(invulnerable_ticks);
+                player.set_reload_ticks(reload_ticks);
+                for (effect, ticks) in effects {
+                    player.add_effect(effect, ticks);
+                }
+                cheats.push(flags);
+                player
+            })
+            .collect();
+        game.set_cheats(cheats);
+        game.enemies = self.enemies;
+        game.flow_field = None;
+        game.projectiles = self.projectiles;
+        if let Some((level, level_count, start_score, target_score)) = self.level {
+            game.level = level;
+            game.level_count = level_count;
+            game.level_start_score = start_score;
+            game.target_score = Some(target_score);
+        }
+        Ok(())
+    }
+}
+
+struct Fields<'a> {
+    line: usize,
+    words: SplitWhitespace<'a>,
+}
+
+impl Fields<'_> {
+    fn next<T: FromStr>(&mut self) -> io::Result<T> {
+        let line = self.line;
+        let word = self
+            .words
+            .next()
+            .ok_or_else(|| invalid(line, "missing field".to_string()))?;
+        word.parse()
+            .map_err(|_| invalid(line, format!("invalid field `{}`", word)))
+    }
+}
+
+fn invalid(line: usize, message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("save line {}: {}", line, message))
+}
+
+fn invalid_level(level: usize, level_count: usize) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("save is on level {} of {}", level, level_count))
+}
+
+fn missing(what: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("save is missing the `{}` line", what))
+}
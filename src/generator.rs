@@ -0,0 +1,358 @@
+use rand::{Rng, RngCore};
+
+use crate::{grid::Grid, point::point::Point2d};
+
+/// Lays out the walls of an arena. The grid handed to `generate` already has
+/// its outer ring blocked, which generators should leave alone. Whatever a
+/// generator produces, the game afterwards walls off every cell the player
+/// cannot reach, see `connect`.
+pub trait Generator {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore);
+}
+
+fn interior(grid: &Grid) -> impl Iterator<Item = Point2d<u16>> + '_ {
+    grid.cells().filter(|&cell| interior_cell(grid, cell))
+}
+
+fn interior_cell(grid: &Grid, cell: Point2d<u16>) -> bool {
+    cell.x > 0 && cell.y > 0 && cell.x < grid.width() - 1 && cell.y < grid.height() - 1
+}
+
+/// Single walls dropped uniformly at random.
+pub struct Scatter {
+    walls: u16,
+}
+
+impl Scatter {
+    pub fn new(walls: u16) -> Self {
+        Self { walls }
+    }
+}
+
+impl Generator for Scatter {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore) {
+        if grid.width() < 3 || grid.height() < 3 {
+            return;
+        }
+        for _ in 0..self.walls {
+            let x = rng.gen_range(1..grid.width() - 1);
+            let y = rng.gen_range(1..grid.height() - 1);
+            grid.block(Point2d::new(x, y));
+        }
+    }
+}
+
+/// Organic caves grown by a cellular automaton: random noise is smoothed by
+/// repeatedly turning cells into walls when most of their neighbours are.
+pub struct Cave {
+    fill: f64,
+    iterations: u8,
+}
+
+impl Cave {
+    /// `fill` is the share of cells that start out as walls, 0.45 gives open caves.
+    pub fn new(fill: f64, iterations: u8) -> Self {
+        Self { fill, iterations }
+    }
+}
+
+impl Default for Cave {
+    fn default() -> Self {
+        Self::new(0.45, 4)
+    }
+}
+
+impl Generator for Cave {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore) {
+        let cells: Vec<_> = interior(grid).collect();
+        for &cell in &cells {
+            grid.set_blocked(cell, rng.gen_bool(self.fill.clamp(0.0, 1.0)));
+        }
+        for _ in 0..self.iterations {
+            let previous = grid.clone();
+            for &cell in &cells {
+                // cells off the grid count as walls, which keeps the edges closed
+                let walls = 8 - previous
+                    .neighbours(cell)
+                    .filter(|&neighbour| !previous.is_blocked(neighbour))
+                    .count();
+                grid.set_blocked(cell, walls >= 5);
+            }
+        }
+    }
+}
+
+/// Rectangular rooms joined in order by L-shaped corridors.
+pub struct RoomsAndCorridors {
+    rooms: u16,
+    min_size: u16,
+    max_size: u16,
+}
+
+impl RoomsAndCorridors {
+    pub fn new(rooms: u16, min_size: u16, max_size: u16) -> Self {
+        Self {
+            rooms,
+            min_size: min_size.max(1),
+            max_size: max_size.max(min_size.max(1)),
+        }
+    }
+}
+
+impl Default for RoomsAndCorridors {
+    fn default() -> Self {
+        Self::new(8, 4, 10)
+    }
+}
+
+impl Generator for RoomsAndCorridors {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore) {
+        let cells: Vec<_> = interior(grid).collect();
+        cells.iter().for_each(|&cell| grid.block(cell));
+        let (inner_width, inner_height) = (grid.width().saturating_sub(2), grid.height().saturating_sub(2));
+        if inner_width == 0 || inner_height == 0 {
+            return;
+        }
+
+        let mut previous: Option<Point2d<u16>> = None;
+        for _ in 0..self.rooms {
+            let width = rng.gen_range(self.min_size..=self.max_size).min(inner_width);
+            let height = rng.gen_range(self.min_size..=self.max_size).min(inner_height);
+            let left = rng.gen_range(1..=1 + inner_width - width);
+            let top = rng.gen_range(1..=1 + inner_height - height);
+            for y in top..top + height {
+                for x in left..left + width {
+                    grid.unblock(Point2d::new(x, y));
+                }
+            }
+
+            let center = Point2d::new(left + width / 2, top + height / 2);
+            if let Some(from) = previous {
+                // go horizontal or vertical first at random so corridors vary
+                let corner = if rng.gen_bool(0.5) {
+                    Point2d::new(center.x, from.y)
+                } else {
+                    Point2d::new(from.x, center.y)
+                };
+                carve_line(grid, from, corner);
+                carve_line(grid, corner, center);
+            }
+            previous = Some(center);
+        }
+    }
+}
+
+// clears a horizontal or vertical run of cells, both ends included
+fn carve_line(grid: &mut Grid, from: Point2d<u16>, to: Point2d<u16>) {
+    for y in from.y.min(to.y)..=from.y.max(to.y) {
+        for x in from.x.min(to.x)..=from.x.max(to.x) {
+            grid.unblock(Point2d::new(x, y));
+        }
+    }
+}
+
+/// A perfect maze carved by a randomized depth-first search, with a share
+/// of its dead ends knocked open so enemies cannot trap the player as easily.
+pub struct Maze {
+    braid: f64,
+}
+
+impl Maze {
+    /// `braid` is the chance of opening up each dead end, 0 keeps the maze perfect.
+    pub fn new(braid: f64) -> Self {
+        Self { braid }
+    }
+}
+
+impl Default for Maze {
+    fn default() -> Self {
+        Self::new(0.3)
+    }
+}
+
+impl Generator for Maze {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore) {
+        let cells: Vec<_> = interior(grid).collect();
+        cells.iter().for_each(|&cell| grid.block(cell));
+        if grid.width() < 3 || grid.height() < 3 {
+            return;
+        }
+
+        // passages run through odd coordinates, walls sit on the even ones
+        let is_room = |x: i32, y: i32, grid: &Grid| {
+            x > 0 && y > 0 && x % 2 == 1 && y % 2 == 1 && x < grid.width() as i32 - 1 && y < grid.height() as i32 - 1
+        };
+        const STEPS: [(i32, i32); 4] = [(0, -2), (2, 0), (0, 2), (-2, 0)];
+        let start = Point2d::new(1u16, 1u16);
+        grid.unblock(start);
+        let mut stack = vec![start];
+        while let Some(&cell) = stack.last() {
+            let (x, y) = (cell.x as i32, cell.y as i32);
+            let unvisited: Vec<_> = STEPS
+                .iter()
+                .map(|(dx, dy)| (x + dx, y + dy))
+                .filter(|&(nx, ny)| is_room(nx, ny, grid) && grid.is_blocked(Point2d::new(nx as u16, ny as u16)))
+                .collect();
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let (nx, ny) = unvisited[rng.gen_range(0..unvisited.len())];
+            grid.unblock(Point2d::new(((x + nx) / 2) as u16, ((y + ny) / 2) as u16));
+            let next = Point2d::new(nx as u16, ny as u16);
+            grid.unblock(next);
+            stack.push(next);
+        }
+
+        for cell in cells {
+            if grid.is_blocked(cell) || !is_room(cell.x as i32, cell.y as i32, grid) {
+                continue;
+            }
+            let (x, y) = (cell.x as i32, cell.y as i32);
+            let walls: Vec<_> = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .into_iter()
+                .map(|(dx, dy)| Point2d::new((x + dx) as u16, (y + dy) as u16))
+                .filter(|&wall| grid.is_blocked(wall) && interior_cell(grid, wall))
+                .collect();
+            if walls.len() == 3 && rng.gen_bool(self.braid.clamp(0.0, 1.0)) {
+                grid.unblock(walls[rng.gen_range(0..walls.len())]);
+            }
+        }
+    }
+}
+
+/// Random blocks placed in one quarter of the arena and mirrored into the
+/// other three, so every side of the arena plays the same.
+pub struct SymmetricArena {
+    blocks: u16,
+    max_size: u16,
+}
+
+impl SymmetricArena {
+    pub fn new(blocks: u16, max_size: u16) -> Self {
+        Self {
+            blocks,
+            max_size: max_size.max(1),
+        }
+    }
+}
+
+impl Default for SymmetricArena {
+    fn default() -> Self {
+        Self::new(6, 4)
+    }
+}
+
+impl Generator for SymmetricArena {
+    fn generate(&self, grid: &mut Grid, rng: &mut dyn RngCore) {
+        let (width, height) = (grid.width(), grid.height());
+        let (half_width, half_height) = (width / 2, height / 2);
+        if half_width < 2 || half_height < 2 {
+            return;
+        }
+        for _ in 0..self.blocks {
+            let block_width = rng.gen_range(1..=self.max_size).min(half_width - 1);
+            let block_height = rng.gen_range(1..=self.max_size).min(half_height - 1);
+            let left = rng.gen_range(1..=half_width - block_width);
+            let top = rng.gen_range(1..=half_height - block_height);
+            for y in top..top + block_height {
+                for x in left..left + block_width {
+                    for (mx, my) in [(x, y), (width - 1 - x, y), (x, height - 1 - y), (width - 1 - x, height - 1 - y)] {
+                        grid.block(Point2d::new(mx, my));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Makes sure everything spawns in one connected region. The region is the
+/// one holding `spawn`, or the largest one if `spawn` ended up inside a wall,
+/// in which case the nearest cell of that region is returned as the new spawn.
+/// Every free cell outside of the region is walled up.
+pub fn connect(grid: &mut Grid, spawn: Point2d<u16>) -> Option<Point2d<u16>> {
+    let mut region = grid.flood_fill(spawn);
+    let mut spawn = spawn;
+    if grid.is_blocked(spawn) {
+        let mut seen = vec![false; region.len()];
+        let mut largest = 0;
+        for cell in grid.cells() {
+            if grid.is_blocked(cell) || seen[grid.index(cell)] {
+                continue;
+            }
+            let candidate = grid.flood_fill(cell);
+            let size = candidate.iter().filter(|&&reached| reached).count();
+            for (seen, reached) in seen.iter_mut().zip(&candidate) {
+                *seen |= *reached;
+            }
+            if size > largest {
+                largest = size;
+                region = candidate;
+            }
+        }
+        let distance = |cell: Point2d<u16>| {
+            (cell.x as i32 - spawn.x as i32).pow(2) + (cell.y as i32 - spawn.y as i32).pow(2)
+        };
+        spawn = grid
+            .cells()
+            .filter(|&cell| region[grid.index(cell)])
+            .min_by_key(|&cell| distance(cell))?;
+    }
+    let cells: Vec<_> = grid.cells().collect();
+    for cell in cells {
+        if !region[grid.index(cell)] {
+            grid.block(cell);
+        }
+    }
+    Some(spawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn walled(width: u16, height: u16) -> Grid {
+        let mut grid = Grid::new(width, height);
+        let cells: Vec<_> = grid.cells().filter(|&cell| !interior_cell(&grid, cell)).collect();
+        for cell in cells {
+            grid.block(cell);
+        }
+        grid
+    }
+
+    #[test]
+    fn every_free_cell_is_reachable_after_connecting() {
+        let generators: [Box<dyn Generator>; 5] = [
+            Box::new(Scatter::new(300)),
+            Box::<Cave>::default(),
+            Box::<RoomsAndCorridors>::default(),
+            Box::<Maze>::default(),
+            Box::<SymmetricArena>::default(),
+        ];
+        for generator in &generators {
+            for seed in 0..10 {
+                let mut grid = walled(41, 21);
+                generator.generate(&mut grid, &mut ChaCha8Rng::seed_from_u64(seed));
+                let spawn = connect(&mut grid, Point2d::new(20, 10)).unwrap();
+                assert!(!grid.is_blocked(spawn));
+                let reached = grid.flood_fill(spawn);
+                assert!(grid.cells().all(|cell| grid.is_blocked(cell) || reached[grid.index(cell)]));
+            }
+        }
+    }
+
+    #[test]
+    fn spawns_inside_walls_move_to_the_largest_region() {
+        let mut grid = walled(12, 7);
+        // a column of wall splits the arena into 2 and 7 columns
+        for y in 1..6 {
+            grid.block(Point2d::new(3, y));
+        }
+        let spawn = connect(&mut grid, Point2d::new(3, 3)).unwrap();
+        assert_eq!(spawn, Point2d::new(4, 3));
+        assert!(grid.is_blocked(Point2d::new(1, 3)) && grid.is_blocked(Point2d::new(2, 3)));
+    }
+}
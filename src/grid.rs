@@ -0,0 +1,128 @@
+use crate::{point::point::Point2d, traits::Position, unit::Wall};
+
+/// Which cells of the arena are blocked by walls, for lookups that would
+/// otherwise scan every wall.
+#[derive(Clone)]
+pub struct Grid {
+    width: u16,
+    height: u16,
+    blocked: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            blocked: vec![false; width as usize * height as usize],
+        }
+    }
+
+    pub fn from_walls(width: u16, height: u16, walls: &[Wall]) -> Self {
+        let mut grid = Self::new(width, height);
+        for wall in walls {
+            grid.block(wall.position());
+        }
+        grid
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn contains(&self, cell: Point2d<u16>) -> bool {
+        cell.x < self.width && cell.y < self.height
+    }
+
+    pub fn block(&mut self, cell: Point2d<u16>) {
+        self.set_blocked(cell, true);
+    }
+
+    pub fn unblock(&mut self, cell: Point2d<u16>) {
+        self.set_blocked(cell, false);
+    }
+
+    pub fn set_blocked(&mut self, cell: Point2d<u16>, blocked: bool) {
+        if self.contains(cell) {
+            let index = self.index(cell);
+            self.blocked[index] = blocked;
+        }
+    }
+
+    pub fn blocked_cells(&self) -> impl Iterator<Item = Point2d<u16>> + '_ {
+        self.cells().filter(|&cell| self.is_blocked(cell))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Point2d<u16>> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Point2d::new(x, y)))
+    }
+
+    /// The free cell closest to `cell`, which is `cell` itself when it is free.
+    pub fn nearest_free(&self, cell: Point2d<u16>) -> Option<Point2d<u16>> {
+        let distance = |other: &Point2d<u16>| {
+            (other.x as i32 - cell.x as i32).pow(2) + (other.y as i32 - cell.y as i32).pow(2)
+        };
+        self.cells()
+            .filter(|&other| !self.is_blocked(other))
+            .min_by_key(distance)
+    }
+
+    /// Every free cell reachable from `start` through edge-sharing free cells,
+    /// as a mask indexed like the grid. Empty if `start` itself is blocked.
+    pub fn flood_fill(&self, start: Point2d<u16>) -> Vec<bool> {
+        let mut reached = vec![false; self.blocked.len()];
+        if self.is_blocked(start) {
+            return reached;
+        }
+        reached[self.index(start)] = true;
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            let (x, y) = (cell.x as i32, cell.y as i32);
+            for (x, y) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if x < 0 || y < 0 {
+                    continue;
+                }
+                let neighbour = Point2d::new(x as u16, y as u16);
+                if !self.is_blocked(neighbour) && !reached[self.index(neighbour)] {
+                    reached[self.index(neighbour)] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Cells outside of the arena count as blocked.
+    pub fn is_blocked(&self, cell: Point2d<u16>) -> bool {
+        !self.contains(cell) || self.blocked[self.index(cell)]
+    }
+
+    /// The cell a continuous position is drawn in, if it lies on the grid at all.
+    pub fn cell_at(&self, position: Point2d<f64>) -> Option<Point2d<u16>> {
+        let position = position.round();
+        if position.x < 0.0 || position.y < 0.0 {
+            return None;
+        }
+        let cell = position.to_u16();
+        self.contains(cell).then_some(cell)
+    }
+
+    /// The in-bounds cells around `cell`, diagonals included.
+    pub fn neighbours(&self, cell: Point2d<u16>) -> impl Iterator<Item = Point2d<u16>> + '_ {
+        const OFFSETS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+        OFFSETS.into_iter().filter_map(move |(dx, dy)| {
+            let (x, y) = (cell.x as i32 + dx, cell.y as i32 + dy);
+            (x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32)
+                .then(|| Point2d::new(x as u16, y as u16))
+        })
+    }
+
+    pub(crate) fn index(&self, cell: Point2d<u16>) -> usize {
+        cell.y as usize * self.width as usize + cell.x as usize
+    }
+}
@@ -1,23 +1,82 @@
 #![allow(non_snake_case, dead_code)]
 
 pub mod Hud {
+    use std::time::Duration;
+
     use crate::{point::point::Point2d, traits::Position, unit::player::Player::Player};
 
     pub struct Hud<'a> {
-        score: u32,
-        player: &'a Player,
+        // every player with their own score, one section each
+        players: Vec<(u32, &'a Player)>,
+        level: Option<(usize, usize)>,
+        tick_interval: Duration,
         y_position: u16,
     }
     impl<'a> Hud<'a> {
         pub fn new(score: u32, player: &'a Player, y_position: u16) -> Self {
             Self {
-                score,
-                player,
+                players: vec![(score, player)],
+                level: None,
+                tick_interval: Duration::ZERO,
                 y_position,
             }
         }
+        /// Adds a section for another player.
+        pub fn player(mut self, score: u32, player: &'a Player) -> Self {
+            self.players.push((score, player));
+            self
+        }
+        pub fn level(mut self, level: usize, level_count: usize) -> Self {
+            self.level = Some((level, level_count));
+            self
+        }
+        /// Used to show the time left on effects in seconds.
+        pub fn tick_interval(mut self, tick_interval: Duration) -> Self {
+            self.tick_interval = tick_interval;
+            self
+        }
         pub fn text(&self) -> String {
-            format!("//\\\\//\\\\//\\\\//\\\\//  SCORE: {:2}  //  HEALTH: {:2}  \\\\  SPEED: {:4.1}  \\\\//\\\\//\\\\//\\\\//\\\\", self.score, self.player.health(), self.player.speed())
+            let level = match self.level {
+                Some((level, level_count)) => format!("  LEVEL: {}/{}  //", level, level_count),
+                None => String::new(),
+            };
+            let players: Vec<_> = self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(index, (score, player))| {
+                    format!(
+                        "{}  SCORE: {:2}  //  HEALTH: {} {:2}  \\\\  SPEED: {:4.1}  ",
+                        self.label(index),
+                        score,
+                        player.face(),
+                        player.health(),
+                        player.speed()
+                    )
+                })
+                .collect();
+            format!("//\\\\//\\\\//\\\\//\\\\//{}{}\\\\//\\\\//\\\\//\\\\//\\\\", level, players.join("||")) + &self.effects()
+        }
+    }
+    impl Hud<'_> {
+        // sections are only labelled once there is more than one player
+        fn label(&self, index: usize) -> String {
+            match self.players.len() {
+                1 => String::new(),
+                _ => format!("  P{}", index + 1),
+            }
+        }
+        fn effects(&self) -> String {
+            self.players
+                .iter()
+                .enumerate()
+                .flat_map(|(index, (_, player))| {
+                    player.effects().iter().map(move |(effect, ticks)| {
+                        let seconds = (self.tick_interval * *ticks as u32).as_secs_f64();
+                        format!("{}  {}: {:.1}s", self.label(index), effect.name().to_uppercase(), seconds)
+                    })
+                })
+                .collect()
         }
     }
     impl<'a> Position<u16> for Hud<'a> {
@@ -32,3 +91,17 @@ pub mod Hud {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Hud::Hud;
+    use crate::unit::PlayerBuilder;
+
+    #[test]
+    fn the_hud_shows_how_hurt_each_player_is() {
+        let hurt = PlayerBuilder::new().health(3).build();
+        let fine = PlayerBuilder::new().health(10).build();
+        let text = Hud::new(0, &hurt, 0).player(0, &fine).text();
+        assert!(text.contains("HEALTH: 😨  3") && text.contains("HEALTH: 😐 10"), "{}", text);
+    }
+}
@@ -1,37 +1,118 @@
-use std::time::Duration;
+use std::{
+    fmt::{self, Display},
+    io,
+    str::FromStr,
+    time::Duration,
+};
 
-use crossterm::event::{poll, Event, KeyCode, KeyEvent, KeyModifiers, KeyEventKind};
+use crossterm::event::{poll, Event, KeyEventKind};
 
 use crate::unit::Player;
 
-pub fn poll_key_event(duration: Duration) -> Option<KeyEvent> {
-    if poll(duration).ok()? {
-        let event = crossterm::event::read().ok()?;
-        if let Event::Key(key_event) = event {
-            if key_event.kind == KeyEventKind::Press {
-                return Some(key_event);
-            }
+mod keymap;
+
+pub use keymap::{Key, KeyMap};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+    TurnLeft,
+    TurnRight,
+    Accelerate,
+    Decelerate,
+    Fire,
+    ToggleNoclip,
+    ToggleSpeed,
+    ToggleInvincibility,
+    Save,
+    ReloadKeys,
+    Suspend,
+    Quit,
+}
+
+impl Action {
+    const ALL: [Action; 12] = [
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Accelerate,
+        Action::Decelerate,
+        Action::Fire,
+        Action::ToggleNoclip,
+        Action::ToggleSpeed,
+        Action::ToggleInvincibility,
+        Action::Save,
+        Action::ReloadKeys,
+        Action::Suspend,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::TurnLeft => "turn-left",
+            Action::TurnRight => "turn-right",
+            Action::Accelerate => "accelerate",
+            Action::Decelerate => "decelerate",
+            Action::Fire => "fire",
+            Action::ToggleNoclip => "toggle-noclip",
+            Action::ToggleSpeed => "toggle-speed",
+            Action::ToggleInvincibility => "toggle-invincibility",
+            Action::Save => "save",
+            Action::ReloadKeys => "reload-keys",
+            Action::Suspend => "suspend",
+            Action::Quit => "quit",
         }
     }
-    None
+
+    /// Whether the action reaches outside of the game, to the disk or the
+    /// process. Replays leave these out, playing one back should not
+    /// overwrite a save.
+    pub fn is_external(&self) -> bool {
+        matches!(self, Action::Save | Action::ReloadKeys | Action::Suspend)
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or_else(|| format!("unknown action `{}`", s))
+    }
 }
 
-pub fn handle_key_event(key: KeyEvent, player: &mut Player, quit: &mut bool) {
-    match key.code {
-        KeyCode::Left => player.turn_left(),
-        KeyCode::Right => player.turn_right(),
-        KeyCode::Up => player.accelerate(),
-        KeyCode::Down => player.decelerate(),
-        KeyCode::Char('n') => player.toggle_noclip(),
-        KeyCode::Char('s') => player.toggle_speed(),
-        KeyCode::Char('u') => player.toggle_invincibility(),
-        KeyCode::Char('q') | KeyCode::Esc => *quit = true,
-        KeyCode::Char('c') => {
-            if key.modifiers == KeyModifiers::CONTROL {
-                *quit = true;
-            }
+/// Waits up to `duration` for a key press or the terminal being resized,
+/// other events are dropped.
+pub fn poll_event(duration: Duration) -> io::Result<Option<Event>> {
+    if poll(duration)? {
+        match crossterm::event::read()? {
+            Event::Key(key_event) if key_event.kind != KeyEventKind::Press => {}
+            event @ (Event::Key(_) | Event::Resize(..)) => return Ok(Some(event)),
+            _ => {}
         }
-        _ => {}
     }
+    Ok(None)
 }
 
+pub fn handle_action(action: Action, player: &mut Player, quit: &mut bool) {
+    match action {
+        Action::TurnLeft => player.turn_left(),
+        Action::TurnRight => player.turn_right(),
+        Action::Accelerate => player.accelerate(),
+        Action::Decelerate => player.decelerate(),
+        Action::ToggleNoclip => player.toggle_noclip(),
+        Action::ToggleSpeed => player.toggle_speed(),
+        Action::ToggleInvincibility => player.toggle_invincibility(),
+        // firing spawns a projectile in the game while saving, reloading the
+        // key map and suspending reach outside of it, so they are left to
+        // `Game::step` and its caller
+        Action::Fire | Action::Save | Action::ReloadKeys | Action::Suspend => {}
+        Action::Quit => *quit = true,
+    }
+}
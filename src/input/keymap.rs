@@ -0,0 +1,307 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::Action;
+
+// keys that are written as a name rather than the character they type
+const NAMES: [(&str, KeyCode); 16] = [
+    ("space", KeyCode::Char(' ')),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("insert", KeyCode::Insert),
+    ("delete", KeyCode::Delete),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+const MODIFIERS: [(&str, KeyModifiers); 3] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+];
+
+/// A key together with the modifiers held down with it, written like `q`,
+/// `space`, `left`, `f5` or `ctrl+c` in key map files. Shift is part of the
+/// character for printable keys, `Q` is a shifted `q`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn code(&self) -> KeyCode {
+        self.code
+    }
+
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, modifier) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match (NAMES.iter().find(|(_, code)| *code == self.code), self.code) {
+            (Some((name, _)), _) => write!(f, "{}", name),
+            (None, KeyCode::F(number)) => write!(f, "f{}", number),
+            (None, KeyCode::Char(c)) => write!(f, "{}", c),
+            (None, code) => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // a lone `+` is the key itself rather than a separator
+        while let Some((name, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            let (_, modifier) = MODIFIERS
+                .into_iter()
+                .find(|(modifier, _)| *modifier == name)
+                .ok_or_else(|| format!("unknown modifier `{}` in `{}`", name, s))?;
+            modifiers |= modifier;
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match NAMES.iter().find(|(name, _)| *name == rest) {
+                Some((_, code)) => *code,
+                None => rest
+                    .strip_prefix('f')
+                    .and_then(|number| number.parse().ok())
+                    .filter(|number| (1..=24).contains(number))
+                    .map(KeyCode::F)
+                    .ok_or_else(|| format!("unknown key `{}`", s))?,
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Which key triggers which action for which player. When a key is bound
+/// for several players the binding made last wins, unless its player is not
+/// playing on this keyboard.
+///
+/// Key map files are read on top of the defaults, one command per line:
+/// `bind <key> <action> [<player>]` with players counted from 1, `unbind
+/// <key>` and `clear`, which drops every binding made so far. Lines starting
+/// with `#` are comments.
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    // the most recent binding first
+    bindings: Vec<(Key, usize, Action)>,
+    // the file the map was loaded from, read again by `reload`
+    path: Option<PathBuf>,
+}
+
+impl KeyMap {
+    /// A map without any bindings.
+    pub fn empty() -> Self {
+        Self {
+            bindings: vec![],
+            path: None,
+        }
+    }
+
+    /// Makes `key` trigger `action` for the player with index `player`,
+    /// replacing what it did for that player before.
+    pub fn bind(&mut self, key: Key, player: usize, action: Action) {
+        self.bindings.retain(|&(bound, index, _)| bound != key || index != player);
+        self.bindings.insert(0, (key, player, action));
+    }
+
+    /// Removes every binding of `key`.
+    pub fn unbind(&mut self, key: Key) {
+        self.bindings.retain(|&(bound, _, _)| bound != key);
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Every binding, the one that wins first.
+    pub fn bindings(&self) -> &[(Key, usize, Action)] {
+        &self.bindings
+    }
+
+    /// The action `key` triggers and the index of the player it is for,
+    /// considering only the first `players` players.
+    pub fn lookup(&self, key: impl Into<Key>, players: usize) -> Option<(usize, Action)> {
+        let key = key.into();
+        self.bindings
+            .iter()
+            .find(|&&(bound, player, _)| bound == key && player < players)
+            .map(|&(_, player, action)| (player, action))
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut key_map = Self::parse(&fs::read_to_string(path)?)?;
+        key_map.path = Some(path.to_path_buf());
+        Ok(key_map)
+    }
+
+    /// Reads the file the map was loaded from again, so it can be edited
+    /// while the game runs. Keeps the current bindings if that fails.
+    pub fn reload(&mut self) -> io::Result<()> {
+        if let Some(path) = self.path.clone() {
+            *self = Self::load(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(ErrorKind::InvalidData, format!("key map line {}: {}", line, message))
+        };
+        let mut key_map = Self::default();
+        let lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty() && !text.starts_with('#')) {
+            let words: Vec<_> = text.split_whitespace().collect();
+            match words[..] {
+                ["clear"] => key_map.clear(),
+                ["unbind", key] => key_map.unbind(key.parse().map_err(|e| invalid(line, e))?),
+                ["bind", key, action] | ["bind", key, action, _] => {
+                    let key = key.parse().map_err(|e| invalid(line, e))?;
+                    let action = action.parse().map_err(|e| invalid(line, e))?;
+                    let player = match words.get(3) {
+                        Some(player) => player
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|&player| player > 0)
+                            .ok_or_else(|| invalid(line, format!("invalid player `{}`", player)))?,
+                        None => 1,
+                    };
+                    key_map.bind(key, player - 1, action);
+                }
+                _ => {
+                    return Err(invalid(
+                        line,
+                        format!("expected `bind <key> <action> [<player>]`, `unbind <key>` or `clear`, found `{}`", text),
+                    ))
+                }
+            }
+        }
+        Ok(key_map)
+    }
+}
+
+/// The first player steers with the arrow keys and fires with space. With
+/// two players on one keyboard the second one steers with WASD and fires
+/// with F, which takes the `s` key away from the speed limit cheat.
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut key_map = Self::empty();
+        let first = [
+            (Key::from(KeyCode::Left), Action::TurnLeft),
+            (Key::from(KeyCode::Right), Action::TurnRight),
+            (Key::from(KeyCode::Up), Action::Accelerate),
+            (Key::from(KeyCode::Down), Action::Decelerate),
+            (Key::from(KeyCode::Char(' ')), Action::Fire),
+            (Key::from(KeyCode::Char('n')), Action::ToggleNoclip),
+            (Key::from(KeyCode::Char('s')), Action::ToggleSpeed),
+            (Key::from(KeyCode::Char('u')), Action::ToggleInvincibility),
+            (Key::from(KeyCode::F(5)), Action::Save),
+            (Key::from(KeyCode::F(6)), Action::ReloadKeys),
+            (Key::from(KeyCode::Char('q')), Action::Quit),
+            (Key::from(KeyCode::Esc), Action::Quit),
+            (Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL), Action::Quit),
+            (Key::new(KeyCode::Char('z'), KeyModifiers::CONTROL), Action::Suspend),
+        ];
+        let second = [
+            (Key::from(KeyCode::Char('a')), Action::TurnLeft),
+            (Key::from(KeyCode::Char('d')), Action::TurnRight),
+            (Key::from(KeyCode::Char('w')), Action::Accelerate),
+            (Key::from(KeyCode::Char('s')), Action::Decelerate),
+            (Key::from(KeyCode::Char('f')), Action::Fire),
+        ];
+        for (key, action) in first {
+            key_map.bind(key, 0, action);
+        }
+        for (key, action) in second {
+            key_map.bind(key, 1, action);
+        }
+        key_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_map_files_bind_on_top_of_the_defaults() {
+        let key_map = KeyMap::parse("# comment\nunbind q\nbind ctrl+x quit\nbind j turn-left 2\n").unwrap();
+        assert_eq!(key_map.lookup(Key::from(KeyCode::Char('q')), 1), None);
+        assert_eq!(
+            key_map.lookup(Key::new(KeyCode::Char('x'), KeyModifiers::CONTROL), 1),
+            Some((0, Action::Quit))
+        );
+        assert_eq!(key_map.lookup(Key::from(KeyCode::Char('j')), 2), Some((1, Action::TurnLeft)));
+        assert_eq!(key_map.lookup(Key::from(KeyCode::Left), 1), Some((0, Action::TurnLeft)));
+    }
+
+    #[test]
+    fn broken_key_maps_say_which_line_is_wrong() {
+        let broken = [
+            ("bind f99 quit", "key map line 1: unknown key `f99`"),
+            ("clear\nbind hyper+x quit", "key map line 2: unknown modifier `hyper` in `hyper+x`"),
+            ("bind x dance", "key map line 1: unknown action `dance`"),
+            ("bind x quit 0", "key map line 1: invalid player `0`"),
+            ("unbind", "key map line 1: expected `bind <key> <action> [<player>]`"),
+        ];
+        for (text, message) in broken {
+            let error = KeyMap::parse(text).err().map(|e| e.to_string());
+            assert!(error.as_deref().is_some_and(|e| e.starts_with(message)), "{:?} for `{}`", error, text);
+        }
+    }
+}
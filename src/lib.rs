@@ -1,9 +1,17 @@
+pub mod collision;
+pub mod config;
 pub mod game;
+pub mod generator;
+pub mod grid;
+pub mod input;
+pub mod map;
+pub mod net;
+pub mod pathfinding;
+pub mod point;
+pub mod replay;
+pub mod traits;
 pub mod unit;
 
-mod input;
 mod hud;
 mod macros;
-mod point;
-mod traits;
 mod ui;
@@ -1,17 +1,62 @@
-use application::{game, unit::Enemy};
+use application::{config::{self, Config}, game::{Game, GameError}, net};
 
 fn main() {
-    let mut game = game::Game::builder()
-        .n_random_walls(30)
-        .height(40)
-        .player_starting_health(10)
-        .player_starting_speed(0.5)
-        .enemies(
-            (1..12)
-                .map(|i| Enemy::with_speed(i as f64 * 0.05))
-                .collect(),
-        )
-        .update_interval(std::time::Duration::from_millis(70))
-        .build();
-    game.run();
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}\n\nRun with --help to see every option.", e);
+            std::process::exit(2);
+        }
+    };
+    if config.help {
+        print!("{}", config::HELP);
+        return;
+    }
+
+    if let Some(address) = &config.join {
+        let joined = config
+            .key_map()
+            .and_then(|key_map| net::connect(address, key_map, config.cell_width.unwrap_or_default()).map_err(|e| format!("failed to join {}: {}", address, e)));
+        if let Err(e) = joined {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let game = match &config.load {
+        Some(path) => Game::load(path)
+            .map_err(|e| format!("failed to load save {}: {}", path.display(), e))
+            .and_then(|mut game| {
+                *game.key_map_mut() = config.key_map()?;
+                game.set_cell_width(config.cell_width.unwrap_or_default());
+                if let Some(path) = &config.record {
+                    game.record_replay(path);
+                }
+                Ok(game)
+            }),
+        None => config
+            .game_builder()
+            .and_then(|builder| {
+                builder.build().map_err(|e| match e {
+                    GameError::InvalidReplay(_) => e.to_string(),
+                    _ => format!("invalid arena: {}", e),
+                })
+            }),
+    };
+    let mut game = match game {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = match &config.host {
+        Some(address) => net::serve(&mut game, address).map_err(|e| format!("failed to host on {}: {}", address, e)),
+        None => game.run().map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
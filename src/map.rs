@@ -0,0 +1,167 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use crate::point::point::Point2d;
+
+/// An arena parsed from a text grid, one character per cell:
+///
+/// ```text
+/// #  wall                 .  or space: floor
+/// P  player spawn         1-9  enemy spawn, speed 0.1 per step
+/// *  collectible spot
+/// ```
+///
+/// The outermost ring is always walled by the game, so only walls may be
+/// drawn on it. Shorter lines are padded with floor.
+pub struct Map {
+    pub width: u16,
+    pub height: u16,
+    pub walls: Vec<Point2d<u16>>,
+    pub player: Point2d<u16>,
+    pub enemies: Vec<(Point2d<u16>, f64)>,
+    pub collectible_spots: Vec<Point2d<u16>>,
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    TooSmall { width: usize, height: usize },
+    TooLarge { width: usize, height: usize },
+    UnknownTile { line: usize, column: usize, tile: char },
+    OnBorder { line: usize, column: usize, tile: char },
+    MissingPlayer,
+    MultiplePlayers { line: usize, column: usize },
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "could not read map: {}", e),
+            MapError::TooSmall { width, height } => {
+                write!(f, "map is {}x{}, it must be at least 3x3", width, height)
+            }
+            MapError::TooLarge { width, height } => {
+                write!(f, "map is {}x{}, it must fit in {}x{}", width, height, u16::MAX, u16::MAX)
+            }
+            MapError::UnknownTile { line, column, tile } => {
+                write!(f, "line {}, column {}: unknown tile `{}`", line, column, tile)
+            }
+            MapError::OnBorder { line, column, tile } => write!(
+                f,
+                "line {}, column {}: `{}` is on the outer wall, only `#` may be placed there",
+                line, column, tile
+            ),
+            MapError::MissingPlayer => write!(f, "map has no player spawn `P`"),
+            MapError::MultiplePlayers { line, column } => {
+                write!(f, "line {}, column {}: second player spawn `P`", line, column)
+            }
+        }
+    }
+}
+
+impl Error for MapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MapError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MapError {
+    fn from(e: io::Error) -> Self {
+        MapError::Io(e)
+    }
+}
+
+impl Map {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MapError> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+impl std::str::FromStr for Map {
+    type Err = MapError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = text.trim_end_matches(['\n', '\r']).lines().collect();
+        let height = rows.len();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        if width < 3 || height < 3 {
+            return Err(MapError::TooSmall { width, height });
+        }
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(MapError::TooLarge { width, height });
+        }
+
+        let mut walls = vec![];
+        let mut player = None;
+        let mut enemies = vec![];
+        let mut collectible_spots = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                let (line, column) = (y + 1, x + 1);
+                let position = Point2d::new(x as u16, y as u16);
+                let on_border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                match tile {
+                    '.' | ' ' => {}
+                    '#' if on_border => {}
+                    '#' => walls.push(position),
+                    _ if on_border => return Err(MapError::OnBorder { line, column, tile }),
+                    'P' if player.is_some() => return Err(MapError::MultiplePlayers { line, column }),
+                    'P' => player = Some(position),
+                    '1'..='9' => {
+                        let speed = tile.to_digit(10).unwrap_or_default() as f64 * 0.1;
+                        enemies.push((position, speed));
+                    }
+                    '*' => collectible_spots.push(position),
+                    _ => return Err(MapError::UnknownTile { line, column, tile }),
+                }
+            }
+        }
+
+        Ok(Map {
+            width: width as u16,
+            height: height as u16,
+            walls,
+            player: player.ok_or(MapError::MissingPlayer)?,
+            enemies,
+            collectible_spots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_parse_every_tile() {
+        let map: Map = "#####\n#P1*#\n#.# #\n#####".parse().unwrap();
+        assert_eq!((map.width, map.height), (5, 4));
+        assert_eq!(map.player, Point2d::new(1, 1));
+        assert_eq!(map.enemies, vec![(Point2d::new(2, 1), 0.1)]);
+        assert_eq!(map.collectible_spots, vec![Point2d::new(3, 1)]);
+        assert_eq!(map.walls, vec![Point2d::new(2, 2)]);
+    }
+
+    #[test]
+    fn broken_maps_say_what_is_wrong() {
+        let error = |text: &str| text.parse::<Map>().err().map(|e| e.to_string());
+        assert_eq!(error("##\n##"), Some("map is 2x2, it must be at least 3x3".to_string()));
+        assert_eq!(error("#####\n#P?.#\n#####"), Some("line 2, column 3: unknown tile `?`".to_string()));
+        assert_eq!(
+            error("##P##\n#...#\n#####"),
+            Some("line 1, column 3: `P` is on the outer wall, only `#` may be placed there".to_string())
+        );
+        assert_eq!(error("#####\n#...#\n#####"), Some("map has no player spawn `P`".to_string()));
+        assert_eq!(
+            error("#####\n#P.P#\n#####"),
+            Some("line 2, column 4: second player spawn `P`".to_string())
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::Event;
+
+use crate::{
+    game::{CellWidth, Game, GameError, Status},
+    input::{self, Action, KeyMap},
+    ui::UI,
+};
+
+/// Where `serve` listens and `connect` connects to when no port is given.
+pub const DEFAULT_PORT: u16 = 7878;
+
+// the player the client controls
+const CLIENT_PLAYER: usize = 1;
+
+// the most a frame may claim to hold, well above the save of the biggest
+// arena anyone would play on
+const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Fills in what is missing from a command line address: `DEFAULT_PORT`
+/// when there is no port, `localhost` when there is only a port.
+pub fn with_default_port(address: &str) -> String {
+    if address.parse::<u16>().is_ok() {
+        format!("localhost:{}", address)
+    } else if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
+
+/// Waits for one client on `address`, then runs `game` on this terminal with
+/// the keyboard playing the first player and the client playing the second.
+/// The server owns the only simulation: every tick the client's actions are
+/// applied and what moved is sent back to it. The whole save, walls and all,
+/// is only sent at the start and whenever a campaign moves on to the next
+/// arena. The client disconnecting ends the game like quitting would.
+pub fn serve(game: &mut Game, address: impl ToSocketAddrs) -> Result<(), GameError> {
+    if game.players().len() <= CLIENT_PLAYER {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "a networked game needs a two-player mode",
+        )
+        .into());
+    }
+    let listener = TcpListener::bind(address)?;
+    println!("Waiting for the second player on {}", listener.local_addr()?);
+    let (mut stream, peer) = listener.accept()?;
+    println!("{} joined", peer);
+    stream.set_nodelay(true)?;
+
+    // the client sends one action per line
+    let (sender, receiver) = mpsc::channel();
+    let reader = BufReader::new(stream.try_clone()?);
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            // saving and reloading keys touch the server's disk and
+            // suspending stops the server's process, so only the host may
+            // do it
+            match line.trim().parse::<Action>() {
+                Ok(Action::Save | Action::ReloadKeys | Action::Suspend) | Err(_) => {}
+                Ok(action) => {
+                    if sender.send(action).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = sender.send(Action::Quit);
+    });
+
+    // the level the client last got the arena of
+    let mut sent_level = None;
+    // only the first player is on this keyboard
+    game.run_with(1, |game, actions| {
+        let frame = if sent_level == Some(game.level()) {
+            Frame::State(game.state_to_string())
+        } else {
+            sent_level = Some(game.level());
+            Frame::Arena(game.save_to_string())
+        };
+        // a failed write means the client is gone, which the reader notices
+        let _ = write_frame(&mut stream, &frame);
+        actions.extend(receiver.try_iter().map(|action| (CLIENT_PLAYER, action)));
+    })
+}
+
+/// Joins the game served on `address` as the second player. Key presses are
+/// looked up in `key_map` as the first player's and sent to the server, and
+/// every state it sends back is drawn with `cell_width`, until the server ends
+/// the game or the connection drops.
+pub fn connect(address: impl ToSocketAddrs, mut key_map: KeyMap, cell_width: CellWidth) -> io::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    stream.set_nodelay(true)?;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    thread::spawn(move || {
+        while let Ok(Some(frame)) = read_frame(&mut reader) {
+            if sender.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut ui = UI::new();
+    let mut last = None;
+    let played = ui.prepare().and_then(|_| play(&mut ui, stream, &receiver, &mut key_map, cell_width, &mut last));
+    let restored = ui.restore();
+    played?;
+    restored?;
+    if let Some(game) = last {
+        game.print_outcome(game.status());
+    }
+    Ok(())
+}
+
+fn play(
+    ui: &mut UI,
+    mut stream: TcpStream,
+    frames: &mpsc::Receiver<Frame>,
+    key_map: &mut KeyMap,
+    cell_width: CellWidth,
+    last: &mut Option<Game>,
+) -> io::Result<()> {
+    let mut redraw = false;
+    loop {
+        // every arena has to be read, but only the newest state is worth
+        // drawing
+        let mut latest = None;
+        let connected = loop {
+            match frames.try_recv() {
+                Ok(Frame::Arena(save)) => {
+                    // the host may be playing a campaign, its level count is
+                    // all the client needs of it
+                    let mut game = Game::parse_save(&save)?;
+                    game.set_cell_width(cell_width);
+                    *last = Some(game);
+                    latest = None;
+                    redraw = true;
+                }
+                Ok(Frame::State(state)) => latest = Some(state),
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Disconnected) => break false,
+            }
+        };
+        if let Some(state) = latest {
+            let game = last
+                .as_mut()
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "the server sent a state before the arena"))?;
+            game.load_state(&state)?;
+            redraw = true;
+        }
+        if let Some(game) = last.as_ref().filter(|_| redraw) {
+            let mut frame = ui.frame();
+            game.draw(&mut frame);
+            ui.render(frame)?;
+            redraw = false;
+            if game.status() != Status::Running {
+                return Ok(());
+            }
+        }
+        if !connected {
+            return Ok(());
+        }
+        if ui.handle_signals()? {
+            writeln!(stream, "{}", Action::Quit)?;
+            return Ok(());
+        }
+
+        let key = match input::poll_event(Duration::from_millis(10))? {
+            Some(Event::Key(key)) => key,
+            Some(Event::Resize(columns, rows)) => {
+                ui.resize(columns, rows);
+                redraw = true;
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some((_, action)) = key_map.lookup(key, 1) {
+            if action == Action::ReloadKeys {
+                // the client's keys are its own business, a broken file
+                // keeps the old ones
+                let _ = key_map.reload();
+                continue;
+            }
+            if action == Action::Suspend {
+                ui.suspend()?;
+                redraw = true;
+                continue;
+            }
+            writeln!(stream, "{}", action)?;
+            if action == Action::Quit {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// What the server sends the client every tick.
+enum Frame {
+    /// A whole save, sent when the arena changes, see `Game::save_to_string`.
+    Arena(String),
+    /// Only what moved, see `Game::state_to_string`.
+    State(String),
+}
+
+// an `arena <length>` or `state <length>` line followed by that many bytes
+fn write_frame(stream: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    let (kind, text) = match frame {
+        Frame::Arena(save) => ("arena", save),
+        Frame::State(state) => ("state", state),
+    };
+    write!(stream, "{} {}\n{}", kind, text.len(), text)?;
+    stream.flush()
+}
+
+// `None` once the server hangs up
+fn read_frame(reader: &mut impl BufRead) -> io::Result<Option<Frame>> {
+    let mut header = String::new();
+    if reader.read_line(&mut header)? == 0 {
+        return Ok(None);
+    }
+    let invalid = || {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("expected a frame header, found `{}`", header.trim()),
+        )
+    };
+    let (kind, length) = header.trim().split_once(' ').ok_or_else(invalid)?;
+    let length: usize = length.parse().map_err(|_| invalid())?;
+    if length > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("frame of {} bytes is over the limit of {}", length, MAX_FRAME_LENGTH),
+        ));
+    }
+    let mut text = vec![0; length];
+    reader.read_exact(&mut text)?;
+    let text = String::from_utf8(text).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    match kind {
+        "arena" => Ok(Some(Frame::Arena(text))),
+        "state" => Ok(Some(Frame::State(text))),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameBuilder;
+
+    #[test]
+    fn states_keep_the_client_in_step() {
+        let mut server = GameBuilder::new().seed(2).build().unwrap();
+        let mut sent = vec![];
+        write_frame(&mut sent, &Frame::Arena(server.save_to_string())).unwrap();
+        for _ in 0..20 {
+            server.step(&[Action::Accelerate, Action::Fire]);
+            write_frame(&mut sent, &Frame::State(server.state_to_string())).unwrap();
+        }
+
+        let mut reader = &sent[..];
+        let Some(Frame::Arena(save)) = read_frame(&mut reader).unwrap() else {
+            panic!("expected the arena first");
+        };
+        let mut client = Game::parse_save(&save).unwrap();
+        while let Some(frame) = read_frame(&mut reader).unwrap() {
+            let Frame::State(state) = frame else {
+                panic!("expected only states after the arena");
+            };
+            assert!(!state.contains("wall"));
+            client.load_state(&state).unwrap();
+        }
+        assert_eq!(client.save_to_string(), server.save_to_string());
+    }
+
+    #[test]
+    fn oversized_frames_are_refused() {
+        let mut reader = &b"state 18446744073709551615\n"[..];
+        assert!(read_frame(&mut reader).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::VecDeque;
+
+use crate::{grid::Grid, point::point::Point2d};
+
+/// Distance in steps from every free cell to the nearest of its target cells.
+/// Computed once for the players' cells and shared by every enemy, so the cost
+/// of pathing does not grow with the number of enemies.
+pub struct FlowField {
+    targets: Vec<Point2d<u16>>,
+    width: u16,
+    distances: Vec<Option<u32>>,
+}
+
+impl FlowField {
+    pub fn new(grid: &Grid, target: Point2d<u16>) -> Self {
+        Self::with_targets(grid, vec![target])
+    }
+
+    pub fn with_targets(grid: &Grid, targets: Vec<Point2d<u16>>) -> Self {
+        let mut distances = vec![None; grid.width() as usize * grid.height() as usize];
+        let mut queue = VecDeque::new();
+        for &target in &targets {
+            if !grid.is_blocked(target) && distances[grid.index(target)].is_none() {
+                distances[grid.index(target)] = Some(0);
+                queue.push_back(target);
+            }
+        }
+
+        // breadth first search outwards from all targets at once
+        while let Some(cell) = queue.pop_front() {
+            let distance = distances[grid.index(cell)].unwrap_or_default();
+            for neighbour in grid.neighbours(cell) {
+                let index = grid.index(neighbour);
+                if distances[index].is_none() && Self::passable(grid, cell, neighbour) {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        Self {
+            targets,
+            width: grid.width(),
+            distances,
+        }
+    }
+
+    pub fn targets(&self) -> &[Point2d<u16>] {
+        &self.targets
+    }
+
+    /// Steps to the nearest target, `None` if `cell` cannot reach it.
+    pub fn distance(&self, cell: Point2d<u16>) -> Option<u32> {
+        if cell.x >= self.width {
+            return None;
+        }
+        let index = cell.y as usize * self.width as usize + cell.x as usize;
+        self.distances.get(index).copied().flatten()
+    }
+
+    /// The neighbouring cell one step closer to the nearest target.
+    pub fn next_cell(&self, grid: &Grid, cell: Point2d<u16>) -> Option<Point2d<u16>> {
+        let distance = self.distance(cell)?;
+        grid.neighbours(cell)
+            .filter(|&neighbour| Self::passable(grid, cell, neighbour))
+            .filter_map(|neighbour| Some((neighbour, self.distance(neighbour)?)))
+            .filter(|&(_, d)| d < distance)
+            .min_by_key(|&(_, d)| d)
+            .map(|(neighbour, _)| neighbour)
+    }
+
+    /// Whether a unit can step from `from` to the neighbouring cell `to`.
+    /// Diagonal steps may not cut the corner of a wall.
+    pub(crate) fn passable(grid: &Grid, from: Point2d<u16>, to: Point2d<u16>) -> bool {
+        !grid.is_blocked(to)
+            && !grid.is_blocked(Point2d::new(from.x, to.y))
+            && !grid.is_blocked(Point2d::new(to.x, from.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the cells `next_cell` steps through from `start`, `start` left out
+    fn path(field: &FlowField, grid: &Grid, start: Point2d<u16>) -> Vec<Point2d<u16>> {
+        let mut path = vec![];
+        let mut cell = start;
+        while let Some(next) = field.next_cell(grid, cell) {
+            path.push(next);
+            cell = next;
+        }
+        path
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let mut grid = Grid::new(10, 10);
+        // a wall between start and target, open at the bottom
+        for y in 0..8 {
+            grid.block(Point2d::new(5, y));
+        }
+        let target = Point2d::new(8, 2);
+        let field = FlowField::new(&grid, target);
+        let start = Point2d::new(2, 2);
+        let path = path(&field, &grid, start);
+        assert_eq!(path.last(), Some(&target));
+        assert_eq!(path.len() as u32, field.distance(start).unwrap());
+        assert!(path.iter().all(|&cell| !grid.is_blocked(cell)));
+        assert!(path.iter().any(|cell| cell.y >= 8));
+    }
+
+    #[test]
+    fn paths_do_not_cut_corners() {
+        let mut grid = Grid::new(10, 10);
+        grid.block(Point2d::new(5, 4));
+        let field = FlowField::new(&grid, Point2d::new(5, 5));
+        // stepping diagonally would clip the wall's corner
+        assert_eq!(field.distance(Point2d::new(4, 4)), Some(2));
+        assert_eq!(field.next_cell(&grid, Point2d::new(4, 4)), Some(Point2d::new(4, 5)));
+        // with nothing in the way the diagonal is one step
+        assert_eq!(field.next_cell(&grid, Point2d::new(6, 6)), Some(Point2d::new(5, 5)));
+    }
+
+    #[test]
+    fn walled_off_cells_have_no_path() {
+        let mut grid = Grid::new(10, 10);
+        for cell in grid.neighbours(Point2d::new(5, 5)).collect::<Vec<_>>() {
+            grid.block(cell);
+        }
+        let field = FlowField::new(&grid, Point2d::new(5, 5));
+        let outside = Point2d::new(1, 1);
+        assert_eq!(field.distance(outside), None);
+        assert_eq!(field.next_cell(&grid, outside), None);
+        assert_eq!(path(&field, &grid, outside), vec![]);
+    }
+}
@@ -1,26 +1,23 @@
+#[allow(clippy::module_inception)]
 pub mod point {
-    use std::ops::Sub;
+    use std::ops::{Add, AddAssign, Mul, Neg, Sub};
     use std::fmt::{Display, Formatter, Result};
     use num::Float;
 
+    /// Where something is, headings and velocities are `Vec2`s.
     #[derive(PartialEq, Clone, Copy, Debug)]
     pub struct Point2d<T> {
         pub x: T,
         pub y: T,
-        pub direction: (f64, f64, i8),
     }
     impl<T> Point2d<T> {
         pub fn new(x: T, y: T) -> Self {
-            Self {
-                x,
-                y,
-                direction: (1.00, 0.00, 0),
-            }
+            Self { x, y }
         }
     }
     impl<T: Display + Float> Display for Point2d<T> {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-            write!(f, "P:{}x{} D:{}", self.x.round(), self.y.round(), self.direction.2)
+            write!(f, "P:{}x{}", self.x.round(), self.y.round())
         }
     }
     impl<T: Default> Default for Point2d<T> {
@@ -31,49 +28,34 @@ pub mod point {
             )
         }
     }
-    impl<T: Sub<Output = T>> Sub for Point2d<T> {
-        type Output = Self;
+    /// The offset that leads from `other` to `self`.
+    impl Sub for Point2d<f64> {
+        type Output = Vec2;
         fn sub(self, other: Self) -> Self::Output {
-            Self {
-                x: self.x - other.x,
-                y: self.y - other.y,
-                direction: (
-                    self.direction.0 - other.direction.0,
-                    self.direction.1 - other.direction.1,
-                    self.direction.2 - other.direction.2,
-                ),
-            }
+            Vec2::new(self.x - other.x, self.y - other.y)
         }
     }
-    impl Point2d<f64> {
-        pub fn move_forward(&mut self, speed: f64) {
-            self.x += self.direction.0 * speed;
-            self.y += self.direction.1 * speed;
+    impl Add<Vec2> for Point2d<f64> {
+        type Output = Self;
+        fn add(self, offset: Vec2) -> Self::Output {
+            Self::new(self.x + offset.x, self.y + offset.y)
         }
-        pub fn simulate_move_forward(&self, speed: f64) -> Self {
-            Self::new(
-                self.x + self.direction.0 * speed,
-                self.y + self.direction.1 * speed,
-            )
+    }
+    impl AddAssign<Vec2> for Point2d<f64> {
+        fn add_assign(&mut self, offset: Vec2) {
+            *self = *self + offset;
+        }
+    }
+    impl Point2d<f64> {
+        pub fn distance(&self, other: Self) -> f64 {
+            (*self - other).length()
         }
-        pub fn update_direction(&mut self, turn: i8) {
-            self.direction.2 = (8 + self.direction.2 + turn) % 8;
-            (self.direction.0, self.direction.1) = match self.direction.2 {
-                0 => ( 1.0000,  0.0000),
-                1 => ( 0.7061,  0.7061),
-                2 => ( 0.0000,  1.0000),
-                3 => (-0.7061,  0.7061),
-                4 => (-1.0000,  0.0000),
-                5 => (-0.7061, -0.7061),
-                6 => ( 0.0000, -1.0000),
-                7 => ( 0.7061, -0.7061),
-                _ => ( 0.0000,  0.0000),
-            }
-        }
-        pub fn to_u16(&self) -> Point2d<u16> {
+        /// The cell this position is drawn in, rounding like everything else
+        /// that maps positions to cells.
+        pub fn to_u16(self) -> Point2d<u16> {
             Point2d::new(
-                self.x as u16,
-                self.y as u16,
+                self.x.round() as u16,
+                self.y.round() as u16,
             )
         }
         pub fn round(&self) -> Self {
@@ -83,4 +65,65 @@ pub mod point {
             )
         }
     }
+
+    /// A direction or offset in the plane. Angles are in radians and grow
+    /// clockwise on screen, as y points down.
+    #[derive(PartialEq, Clone, Copy, Debug, Default)]
+    pub struct Vec2 {
+        pub x: f64,
+        pub y: f64,
+    }
+    impl Vec2 {
+        pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+        pub fn new(x: f64, y: f64) -> Self {
+            Self { x, y }
+        }
+        /// The unit vector pointing at `angle`.
+        pub fn from_angle(angle: f64) -> Self {
+            Self::new(angle.cos(), angle.sin())
+        }
+        pub fn length(&self) -> f64 {
+            self.x.hypot(self.y)
+        }
+        /// The unit vector pointing the same way, `None` for the zero vector
+        /// which points nowhere.
+        pub fn normalize(&self) -> Option<Self> {
+            let length = self.length();
+            (length > 0.0 && length.is_finite()).then(|| *self * (1.0 / length))
+        }
+        pub fn dot(&self, other: Self) -> f64 {
+            self.x * other.x + self.y * other.y
+        }
+        pub fn distance(&self, other: Self) -> f64 {
+            (*self - other).length()
+        }
+        pub fn angle(&self) -> f64 {
+            self.y.atan2(self.x)
+        }
+    }
+    impl Add for Vec2 {
+        type Output = Self;
+        fn add(self, other: Self) -> Self::Output {
+            Self::new(self.x + other.x, self.y + other.y)
+        }
+    }
+    impl Sub for Vec2 {
+        type Output = Self;
+        fn sub(self, other: Self) -> Self::Output {
+            Self::new(self.x - other.x, self.y - other.y)
+        }
+    }
+    impl Mul<f64> for Vec2 {
+        type Output = Self;
+        fn mul(self, scale: f64) -> Self::Output {
+            Self::new(self.x * scale, self.y * scale)
+        }
+    }
+    impl Neg for Vec2 {
+        type Output = Self;
+        fn neg(self) -> Self::Output {
+            Self::new(-self.x, -self.y)
+        }
+    }
 }
@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::input::Action;
+
+const HEADER: &str = "replay 1";
+
+/// Every action fed into a game together with the tick it was applied on and
+/// the index of the player it was for, along with the save of the game the
+/// first of them was applied to. Played back from that state, it reproduces
+/// the original run exactly.
+pub struct Replay {
+    seed: u64,
+    start: Option<String>,
+    events: Vec<(u64, usize, Action)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            start: None,
+            events: vec![],
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The save of the game before the first recorded tick, missing when the
+    /// recording never got to one.
+    pub fn start(&self) -> Option<&str> {
+        self.start.as_deref()
+    }
+
+    pub fn set_start(&mut self, save: String) {
+        self.start = Some(save);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn last_tick(&self) -> Option<u64> {
+        self.events.last().map(|(tick, _, _)| *tick)
+    }
+
+    pub fn record(&mut self, tick: u64, player: usize, action: Action) {
+        self.events.push((tick, player, action));
+    }
+
+    pub fn actions_at(&self, tick: u64) -> Vec<(usize, Action)> {
+        let start = self.events.partition_point(|(t, _, _)| *t < tick);
+        self.events[start..]
+            .iter()
+            .take_while(|(t, _, _)| *t == tick)
+            .map(|(_, player, action)| (*player, *action))
+            .collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(ErrorKind::InvalidData, format!("replay line {}: {}", line, message))
+        };
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).peekable();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            Some((line, other)) => return Err(invalid(line, format!("expected `{}`, found `{}`", HEADER, other))),
+            None => return Err(invalid(1, "empty file".to_string())),
+        }
+        let seed = match lines.next() {
+            Some((line, text)) => text
+                .strip_prefix("seed ")
+                .and_then(|seed| seed.parse().ok())
+                .ok_or_else(|| invalid(line, format!("expected `seed <u64>`, found `{}`", text)))?,
+            None => return Err(invalid(2, "missing seed".to_string())),
+        };
+
+        let mut replay = Self::new(seed);
+        // a recording that never got to its first tick has no starting state
+        if let Some((line, text)) = lines.next_if(|(_, text)| text.starts_with("start")) {
+            let count: usize = text
+                .strip_prefix("start ")
+                .and_then(|count| count.parse().ok())
+                .ok_or_else(|| invalid(line, format!("expected `start <lines>`, found `{}`", text)))?;
+            let save: Vec<_> = lines.by_ref().take(count).map(|(_, text)| text).collect();
+            if save.len() < count {
+                return Err(invalid(line, format!("starting state is cut off after {} lines", save.len())));
+            }
+            replay.set_start(save.join("\n"));
+        }
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+            let words: Vec<_> = text.split_whitespace().collect();
+            let (tick, player, action) = match words[..] {
+                [tick, player, action] => (tick, player, action),
+                _ => return Err(invalid(line, format!("expected `<tick> <player> <action>`, found `{}`", text))),
+            };
+            let tick: u64 = tick
+                .parse()
+                .map_err(|_| invalid(line, format!("invalid tick `{}`", tick)))?;
+            if replay.last_tick().is_some_and(|last| tick < last) {
+                return Err(invalid(line, "ticks must not decrease".to_string()));
+            }
+            let player = player
+                .parse()
+                .map_err(|_| invalid(line, format!("invalid player `{}`", player)))?;
+            let action = action.parse().map_err(|e| invalid(line, e))?;
+            replay.record(tick, player, action);
+        }
+        Ok(replay)
+    }
+}
+
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "seed {}", self.seed)?;
+        if let Some(start) = &self.start {
+            writeln!(f, "start {}", start.lines().count())?;
+            for line in start.lines() {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        for (tick, player, action) in &self.events {
+            writeln!(f, "{} {} {}", tick, player, action)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_starting_state_round_trips() {
+        let mut replay = Replay::new(7);
+        replay.set_start("first\nsecond".to_string());
+        replay.record(3, 1, Action::Fire);
+        let parsed = Replay::parse(&replay.to_string()).unwrap();
+        assert_eq!(parsed.start(), Some("first\nsecond"));
+        assert_eq!(parsed.actions_at(3), vec![(1, Action::Fire)]);
+        assert!(Replay::parse("replay 1\nseed 7\nstart 3\nfirst\nsecond\n").is_err());
+    }
+}
@@ -2,7 +2,6 @@ use num::traits::NumAssign;
 use rand::{
     distributions::{uniform::SampleUniform, Standard},
     prelude::Distribution,
-    rngs::ThreadRng,
     Rng,
 };
 use std::ops::Range;
@@ -12,7 +11,7 @@ use crate::point::point::Point2d;
 pub trait Position<T: NumAssign + Copy + Default> {
     fn position(&self) -> Point2d<T>;
     fn set_position(&mut self, position: Point2d<T>);
-    fn set_rand_position(&mut self, rng: &mut ThreadRng, x_range: Range<T>, y_range: Range<T>)
+    fn set_rand_position<R: Rng + ?Sized>(&mut self, rng: &mut R, x_range: Range<T>, y_range: Range<T>)
     where
         T: PartialOrd + SampleUniform,
         Standard: Distribution<T>,
@@ -1,48 +1,174 @@
 use crate::{
+    game::WEAPONS,
     hud::Hud::Hud,
-    impl_display,
+    point::point::Point2d,
     traits::Position,
-    unit::{Collectible, Enemy, Player, Wall},
+    unit::{Collectible, CollectibleKind, Enemy, Player, Projectile, Wall},
 };
-use std::{fmt::Display, io::Write};
 
-use crossterm::style::Stylize;
+use crossterm::style::{Color, StyledContent, Stylize};
 use num::{traits::NumAssign, NumCast};
 
-pub trait Draw<T: NumAssign + Copy + NumCast + Default>: Position<T> + Display {
-    fn draw(&self, stdout: &mut impl Write) {
+use super::{
+    frame::{char_width, Frame},
+    CellWidth,
+};
+
+// the heading arrows clockwise from east, one per eighth of a turn
+const ARROWS: [char; 8] = ['→', '↘', '↓', '↙', '←', '↖', '↑', '↗'];
+// narrow cells cannot fit the weapons, so players are told apart by colour
+const PLAYER_COLORS: [Color; WEAPONS.len()] = [Color::White, Color::Cyan];
+
+pub trait Draw<T: NumAssign + Copy + NumCast + Default>: Position<T> {
+    fn visible(&self) -> bool {
+        true
+    }
+    /// How many columns it covers from its position rightwards, the one cell
+    /// the game keeps it in for units.
+    fn footprint(&self, cell_width: CellWidth) -> u16 {
+        cell_width.columns()
+    }
+    /// What is drawn from the position rightwards, one styled character per
+    /// glyph. Glyphs that do not fit the footprint are left out.
+    fn glyphs(&self, cell_width: CellWidth) -> Vec<StyledContent<char>>;
+    /// Draws over its footprint relative to `origin`, the screen cell of the
+    /// arena's top left corner, blanking what the glyphs leave free.
+    fn draw(&self, frame: &mut Frame, origin: Point2d<u16>, cell_width: CellWidth) {
+        if !self.visible() {
+            return;
+        }
         let position = self.position();
-        crossterm::queue!(
-            stdout,
-            crossterm::cursor::MoveTo(
-                position
-                    .x
-                    .to_f64()
-                    .expect("could not convert position x to f64")
-                    .round() as u16,
-                position
-                    .y
-                    .to_f64()
-                    .expect("could not convert position y to f64")
-                    .round() as u16,
-            ),
-            crossterm::style::Print(self)
-        )
-        .unwrap();
+        let x = position.x.to_f64().expect("could not convert position x to f64").round();
+        let y = position.y.to_f64().expect("could not convert position y to f64").round();
+        let column = origin.x as f64 + x * cell_width.columns() as f64;
+        let row = origin.y as f64 + y;
+        // noclip takes players anywhere, what is left of or above the arena
+        // or off the screen is not drawn
+        let (width, height) = frame.size();
+        if !(x >= 0.0 && y >= 0.0 && column < width as f64 && row < height as f64) {
+            return;
+        }
+        frame.fill(column as u16, row as u16, self.footprint(cell_width), self.glyphs(cell_width));
+    }
+}
+
+impl Draw<f64> for Player {
+    // blink while recovering from a hit, and drop out of sight once dead
+    fn visible(&self) -> bool {
+        self.is_alive() && (self.invulnerable_ticks() / 2).is_multiple_of(2)
+    }
+    fn glyphs(&self, cell_width: CellWidth) -> Vec<StyledContent<char>> {
+        match cell_width {
+            CellWidth::Narrow => {
+                let arrow = ARROWS[((self.heading() + 22.5) / 45.0).floor() as usize % ARROWS.len()];
+                let index = WEAPONS.iter().position(|&weapon| weapon == self.weapon()).unwrap_or_default();
+                vec![arrow.with(PLAYER_COLORS[index])]
+            }
+            CellWidth::Wide => vec![self.weapon().stylize()],
+        }
+    }
+}
+
+impl Draw<u16> for Collectible {
+    fn glyphs(&self, _: CellWidth) -> Vec<StyledContent<char>> {
+        vec![match self.kind() {
+            CollectibleKind::Heart => '❤'.red(),
+            CollectibleKind::Health => '✚'.green(),
+            CollectibleKind::SpeedBoost => '»'.cyan(),
+            CollectibleKind::Shield => '◊'.blue(),
+            CollectibleKind::ScoreMultiplier => '✖'.yellow(),
+            CollectibleKind::Magnet => '∩'.magenta(),
+        }]
+    }
+}
+
+impl Draw<u16> for Wall {
+    fn glyphs(&self, cell_width: CellWidth) -> Vec<StyledContent<char>> {
+        vec!['▓'.magenta(); cell_width.columns() as usize]
     }
 }
 
-impl Draw<f64> for Player {}
+impl Draw<f64> for Enemy {
+    fn glyphs(&self, _: CellWidth) -> Vec<StyledContent<char>> {
+        vec!['⁂'.dark_green()]
+    }
+}
 
-impl Draw<u16> for Collectible {}
-impl_display!(for Collectible: '❤'.red());
+impl Draw<f64> for Projectile {
+    fn glyphs(&self, _: CellWidth) -> Vec<StyledContent<char>> {
+        vec!['•'.yellow()]
+    }
+}
 
-impl Draw<u16> for Wall {}
-impl_display!(for Wall: '▓'.magenta());
+impl Draw<u16> for Hud<'_> {
+    // text rather than a unit, as wide as it in either cell width
+    fn footprint(&self, _: CellWidth) -> u16 {
+        self.text().chars().map(char_width).sum()
+    }
+    fn glyphs(&self, _: CellWidth) -> Vec<StyledContent<char>> {
+        self.text().chars().map(Stylize::stylize).collect()
+    }
+}
 
-impl Draw<f64> for Enemy {}
-impl_display!(for Enemy: '⁂'.dark_green());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        point::point::Vec2,
+        unit::{CollectibleKind, PlayerBuilder},
+    };
 
-impl Draw<u16> for Hud<'_> {}
-impl_display!(for Hud<'_>: method text);
+    const CELL_WIDTHS: [CellWidth; 2] = [CellWidth::Narrow, CellWidth::Wide];
 
+    fn written(frame: &Frame) -> String {
+        let mut written = vec![];
+        frame.write_changes(None, &mut written).unwrap();
+        String::from_utf8(written).unwrap()
+    }
+
+    // draws `unit` on the cell left of a wall and counts the wall's glyphs
+    // left over
+    fn wall_glyphs_next_to<T>(unit: &impl Draw<T>, cell_width: CellWidth) -> usize
+    where
+        T: NumAssign + Copy + NumCast + Default,
+    {
+        let mut frame = Frame::new(40, 10);
+        Wall::new(3, 1).draw(&mut frame, Point2d::new(1, 1), cell_width);
+        unit.draw(&mut frame, Point2d::new(1, 1), cell_width);
+        written(&frame).matches('▓').count()
+    }
+
+    #[test]
+    fn units_never_draw_over_the_next_cell() {
+        let mut enemy = Enemy::default();
+        enemy.set_position(Point2d::new(2.0, 1.0));
+        let projectile = Projectile::new(Point2d::new(2.0, 1.0), Vec2::new(1.0, 0.0), 1.0, 1);
+        for cell_width in CELL_WIDTHS {
+            let columns = cell_width.columns() as usize;
+            for health in [1, 5, 10] {
+                let player = PlayerBuilder::new().position(2.0, 1.0).health(health).build();
+                assert_eq!(wall_glyphs_next_to(&player, cell_width), columns);
+            }
+            assert_eq!(wall_glyphs_next_to(&enemy, cell_width), columns);
+            assert_eq!(wall_glyphs_next_to(&projectile, cell_width), columns);
+            for kind in [CollectibleKind::Heart, CollectibleKind::SpeedBoost, CollectibleKind::Magnet] {
+                assert_eq!(wall_glyphs_next_to(&Collectible::with_kind(2, 1, kind), cell_width), columns);
+            }
+            assert_eq!(wall_glyphs_next_to(&Wall::new(2, 1), cell_width), 2 * columns);
+        }
+    }
+
+    #[test]
+    fn players_off_the_screen_are_not_drawn() {
+        let blank = written(&Frame::new(40, 10));
+        let positions = [(-3.0, 1.0), (1.0, -3.0), (40000.0, 1.0), (f64::MAX, 1.0), (1.0, 70000.0)];
+        for (x, y) in positions {
+            let player = PlayerBuilder::new().position(x, y).build();
+            for cell_width in CELL_WIDTHS {
+                let mut frame = Frame::new(40, 10);
+                player.draw(&mut frame, Point2d::new(1, 1), cell_width);
+                assert_eq!(written(&frame), blank, "{},{} drawn", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{PrintStyledContent, StyledContent, Stylize},
+    terminal::{Clear, ClearType},
+};
+
+/// How many columns `c` takes up on a terminal. Emoji and East Asian wide
+/// characters take two, everything else one.
+pub fn char_width(c: char) -> u16 {
+    let wide = matches!(
+        u32::from(c),
+        0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD
+    );
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Cell {
+    Blank,
+    Glyph(StyledContent<char>),
+    // the right half of the wide glyph in the cell to the left
+    Continuation,
+}
+
+/// A grid of cells the size of the terminal that a screen is drawn into
+/// before any of it is written out. Drawing outside of it is clipped.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    /// A blank frame of `width` columns and `height` rows.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::Blank; usize::from(width) * usize::from(height)],
+        }
+    }
+
+    /// The columns and rows of the frame.
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Puts `glyph` at column `x` of row `y` and returns how many columns it
+    /// takes up. Wide glyphs it partly covers are blanked out, and a wide
+    /// glyph that does not fit before the right edge is drawn as a blank.
+    pub fn put(&mut self, x: u16, y: u16, glyph: StyledContent<char>) -> u16 {
+        let width = char_width(*glyph.content());
+        if x >= self.width || y >= self.height {
+            return width;
+        }
+        let index = self.index(x, y);
+        if x + width > self.width {
+            self.clear(x, y);
+            return width;
+        }
+        for column in x..x + width {
+            self.clear(column, y);
+        }
+        self.cells[index] = Cell::Glyph(glyph);
+        for cell in &mut self.cells[index + 1..index + usize::from(width)] {
+            *cell = Cell::Continuation;
+        }
+        width
+    }
+
+    /// Puts `glyphs` into the `columns` columns from column `x` of row `y` on,
+    /// leaving out the ones that do not fit and blanking the rest, so nothing
+    /// drawn there before is left over.
+    pub fn fill(&mut self, x: u16, y: u16, columns: u16, glyphs: impl IntoIterator<Item = StyledContent<char>>) {
+        let end = x.saturating_add(columns);
+        let mut column = x;
+        for glyph in glyphs {
+            if column + char_width(*glyph.content()) > end {
+                break;
+            }
+            column += self.put(column, y, glyph);
+        }
+        for column in column..end.min(self.width) {
+            if y < self.height {
+                self.clear(column, y);
+            }
+        }
+    }
+
+    /// Puts `glyphs` next to each other from column `x` of row `y` on.
+    pub fn print(&mut self, x: u16, y: u16, glyphs: impl IntoIterator<Item = StyledContent<char>>) {
+        let mut x = x;
+        for glyph in glyphs {
+            x = x.saturating_add(self.put(x, y, glyph));
+        }
+    }
+
+    /// Puts unstyled `text` from column `x` of row `y` on.
+    pub fn print_str(&mut self, x: u16, y: u16, text: &str) {
+        self.print(x, y, text.chars().map(Stylize::stylize));
+    }
+
+    /// Writes what it takes to turn the screen showing `previous` into this
+    /// frame, everything after clearing the screen without one or when the
+    /// sizes differ.
+    pub fn write_changes(&self, previous: Option<&Frame>, buffer: &mut impl Write) -> io::Result<()> {
+        let previous = previous.filter(|previous| previous.size() == self.size());
+        if previous.is_none() {
+            queue!(buffer, Clear(ClearType::All))?;
+        }
+        // where the terminal's cursor is after the last glyph written
+        let mut cursor = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = self.index(x, y);
+                let glyph = match self.cells[index] {
+                    Cell::Blank => ' '.stylize(),
+                    Cell::Glyph(glyph) => glyph,
+                    Cell::Continuation => continue,
+                };
+                let width = char_width(*glyph.content());
+                let changed = match previous {
+                    // a wide glyph is also redrawn when whatever was under
+                    // its right half changed
+                    Some(previous) => (index..index + usize::from(width)).any(|i| previous.cells[i] != self.cells[i]),
+                    None => self.cells[index] != Cell::Blank,
+                };
+                if !changed {
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    queue!(buffer, MoveTo(x, y))?;
+                }
+                queue!(buffer, PrintStyledContent(glyph))?;
+                // terminals disagree on how wide some characters are, so
+                // the cursor is only trusted after plain ASCII
+                cursor = glyph.content().is_ascii().then_some((x + width, y));
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        usize::from(y) * usize::from(self.width) + usize::from(x)
+    }
+
+    // blanks a cell together with the rest of any wide glyph on it
+    fn clear(&mut self, x: u16, y: u16) {
+        let index = self.index(x, y);
+        match self.cells[index] {
+            Cell::Continuation if x > 0 => self.cells[index - 1] = Cell::Blank,
+            Cell::Glyph(glyph) if char_width(*glyph.content()) > 1 && x + 1 < self.width => {
+                self.cells[index + 1] = Cell::Blank
+            }
+            _ => {}
+        }
+        self.cells[index] = Cell::Blank;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(frame: &Frame, previous: Option<&Frame>) -> Vec<u8> {
+        let mut buffer = vec![];
+        frame.write_changes(previous, &mut buffer).unwrap();
+        buffer
+    }
+
+    fn frame(width: u16, text: &str) -> Frame {
+        let mut frame = Frame::new(width, 1);
+        frame.print_str(0, 0, text);
+        frame
+    }
+
+    #[test]
+    fn unchanged_frames_write_nothing() {
+        let frame = frame(4, "ab😀");
+        assert!(written(&frame, Some(&frame.clone())).is_empty());
+    }
+
+    #[test]
+    fn only_changed_cells_are_written() {
+        let mut expected = vec![];
+        queue!(expected, MoveTo(2, 0), PrintStyledContent('x'.stylize())).unwrap();
+        assert_eq!(written(&frame(4, "abxd"), Some(&frame(4, "abcd"))), expected);
+    }
+
+    #[test]
+    fn wide_glyphs_replace_narrow_ones_and_back() {
+        let mut expected = vec![];
+        queue!(expected, MoveTo(1, 0), PrintStyledContent('😀'.stylize())).unwrap();
+        assert_eq!(written(&frame(4, "a😀d"), Some(&frame(4, "abcd"))), expected);
+
+        // both halves of the wide glyph are written over, the second without
+        // moving since the cursor is already there after plain ASCII
+        let mut expected = vec![];
+        queue!(
+            expected,
+            MoveTo(1, 0),
+            PrintStyledContent('b'.stylize()),
+            PrintStyledContent('c'.stylize()),
+        )
+        .unwrap();
+        assert_eq!(written(&frame(4, "abcd"), Some(&frame(4, "a😀d"))), expected);
+    }
+
+    #[test]
+    fn resized_frames_are_written_after_a_clear() {
+        let mut expected = vec![];
+        queue!(
+            expected,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            PrintStyledContent('a'.stylize()),
+            PrintStyledContent('b'.stylize()),
+        )
+        .unwrap();
+        assert_eq!(written(&frame(4, "ab"), Some(&frame(3, "ab"))), expected);
+        assert_eq!(written(&frame(4, "ab"), None), expected);
+    }
+}
@@ -1,48 +1,298 @@
 pub mod draw;
+pub mod frame;
 
-use std::io::{stdout, Stdout};
+use std::{
+    fmt::{self, Display},
+    io::{self, stdout, Stdout, Write},
+    panic,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Once, OnceLock,
+    },
+};
 
 use crossterm::{
     cursor::{self, MoveTo},
-    execute, queue,
+    execute,
     style::{Color, ResetColor, SetBackgroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType},
 };
+use signal_hook::{consts::signal, flag};
+
+use crate::point::point::Point2d;
+
+use self::frame::Frame;
+
+/// Rows the HUD needs below the arena, counting the gap above it.
+pub const HUD_ROWS: u16 = 3;
+
+/// How many terminal columns an arena cell is drawn across. Units take up
+/// one cell in the game, so they are drawn with glyphs that fill exactly
+/// that many columns.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum CellWidth {
+    /// One column, units are drawn with narrow characters only.
+    #[default]
+    Narrow,
+    /// Two columns, room for an emoji per cell. Narrow characters are
+    /// padded, walls doubled.
+    Wide,
+}
+
+impl CellWidth {
+    const ALL: [CellWidth; 2] = [CellWidth::Narrow, CellWidth::Wide];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellWidth::Narrow => "narrow",
+            CellWidth::Wide => "wide",
+        }
+    }
+
+    pub fn columns(&self) -> u16 {
+        match self {
+            CellWidth::Narrow => 1,
+            CellWidth::Wide => 2,
+        }
+    }
+}
+
+impl Display for CellWidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
 
+impl FromStr for CellWidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CellWidth::ALL
+            .into_iter()
+            .find(|cell_width| cell_width.name() == s)
+            .ok_or_else(|| format!("unknown cell width `{}`, expected narrow or wide", s))
+    }
+}
+
+// whether the terminal is currently set up for the game, shared with the
+// panic hook so that it restores the terminal before the message is printed
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+static SIGNALS: OnceLock<Signals> = OnceLock::new();
+
+// Signal handlers can only be added, never taken back out, so they are
+// registered once and fall back to the default action whenever no game is
+// running.
+struct Signals {
+    // no game owns the terminal, signals act as if they were not caught
+    released: Arc<AtomicBool>,
+    terminate: Arc<AtomicBool>,
+    suspend: Arc<AtomicBool>,
+    resume: Arc<AtomicBool>,
+}
+
+impl Signals {
+    fn register() -> io::Result<Self> {
+        let signals = Self {
+            released: Arc::new(AtomicBool::new(true)),
+            terminate: Arc::new(AtomicBool::new(false)),
+            suspend: Arc::new(AtomicBool::new(false)),
+            resume: Arc::new(AtomicBool::new(false)),
+        };
+        for signal in [signal::SIGINT, signal::SIGTERM] {
+            flag::register_conditional_default(signal, Arc::clone(&signals.released))?;
+            flag::register(signal, Arc::clone(&signals.terminate))?;
+        }
+        #[cfg(unix)]
+        {
+            flag::register_conditional_default(signal::SIGTSTP, Arc::clone(&signals.released))?;
+            flag::register(signal::SIGTSTP, Arc::clone(&signals.suspend))?;
+            flag::register(signal::SIGCONT, Arc::clone(&signals.resume))?;
+        }
+        Ok(signals)
+    }
+}
+
+/// Owns the terminal while a game is drawn on it. The terminal is put back
+/// the way it was by `restore`, when the `UI` is dropped, or before a panic
+/// message is printed, whichever comes first.
+///
+/// While prepared, SIGINT and SIGTERM are caught so the game can end as if
+/// it was quit, and on Unix SIGTSTP hands the terminal back before the
+/// process stops and takes it again on SIGCONT. See `handle_signals`.
 pub struct UI {
     stdout: Stdout,
+    // columns and rows, as of the last resize
+    size: (u16, u16),
+    // what the terminal shows, `None` when it has to be drawn from scratch
+    shown: Option<Frame>,
 }
 
 impl UI {
     pub fn new() -> Self {
         let stdout = stdout();
-        Self { stdout }
-    }
-
-    pub fn prepare(&mut self) {
-        enable_raw_mode().unwrap();
-        execute!(
-            self.stdout,
-            SetBackgroundColor(Color::Black),
-            Clear(ClearType::All),
-            cursor::Hide,
-        )
-        .unwrap();
-    }
-
-    pub fn clear(&mut self) {
-        queue!(self.stdout, Clear(ClearType::All)).unwrap();
-    }
-
-    pub fn restore(&mut self) {
-        execute!(
-            self.stdout,
-            ResetColor,
-            Clear(ClearType::All),
-            MoveTo(0, 0),
-            cursor::Show
-        )
-        .unwrap();
-        disable_raw_mode().unwrap();
+        Self {
+            stdout,
+            size: (0, 0),
+            shown: None,
+        }
+    }
+
+    /// The terminal size in columns and rows.
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Records a size reported by a resize event.
+    pub fn resize(&mut self, columns: u16, rows: u16) {
+        self.size = (columns, rows);
+    }
+
+    /// A blank frame the size of the terminal to draw the next screen into.
+    pub fn frame(&self) -> Frame {
+        Frame::new(self.size.0, self.size.1)
+    }
+
+    /// Shows `frame`, writing only the cells that differ from the frame shown
+    /// before it.
+    pub fn render(&mut self, frame: Frame) -> io::Result<()> {
+        let mut buffer: Vec<u8> = Vec::new();
+        frame.write_changes(self.shown.as_ref(), &mut buffer)?;
+        self.stdout.write_all(&buffer)?;
+        self.stdout.flush()?;
+        self.shown = Some(frame);
+        Ok(())
+    }
+
+    pub fn prepare(&mut self) -> io::Result<()> {
+        PANIC_HOOK.call_once(|| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                let _ = leave(&mut stdout());
+                previous(info);
+            }));
+        });
+        if SIGNALS.get().is_none() {
+            let _ = SIGNALS.set(Signals::register()?);
+        }
+        if let Some(signals) = SIGNALS.get() {
+            for flag in [&signals.terminate, &signals.suspend, &signals.resume] {
+                flag.store(false, Ordering::SeqCst);
+            }
+            signals.released.store(false, Ordering::SeqCst);
+        }
+        self.enter()
+    }
+
+    /// Leaves raw mode even if resetting the screen fails. Signals take their
+    /// default action again afterwards.
+    pub fn restore(&mut self) -> io::Result<()> {
+        if let Some(signals) = SIGNALS.get() {
+            signals.released.store(true, Ordering::SeqCst);
+        }
+        leave(&mut self.stdout)
+    }
+
+    /// Deals with the signals that arrived since the last call, suspending
+    /// on SIGTSTP and taking the terminal back after being continued. Returns
+    /// whether SIGINT or SIGTERM asked the game to end. The screen is cleared
+    /// after a suspend, so the caller should redraw it.
+    pub fn handle_signals(&mut self) -> io::Result<bool> {
+        let Some(signals) = SIGNALS.get() else {
+            return Ok(false);
+        };
+        if signals.suspend.swap(false, Ordering::SeqCst) {
+            self.suspend()?;
+        }
+        // the shell may have reset the terminal while the process was
+        // stopped by something other than SIGTSTP
+        if signals.resume.swap(false, Ordering::SeqCst) {
+            self.enter()?;
+        }
+        Ok(signals.terminate.load(Ordering::SeqCst))
+    }
+
+    /// Hands the terminal back to the shell and stops the process like
+    /// Ctrl-Z does outside of raw mode, then takes the terminal again once
+    /// the process is continued.
+    pub fn suspend(&mut self) -> io::Result<()> {
+        leave(&mut self.stdout)?;
+        #[cfg(unix)]
+        signal_hook::low_level::emulate_default_handler(signal::SIGTSTP)?;
+        if let Some(signals) = SIGNALS.get() {
+            signals.resume.store(false, Ordering::SeqCst);
+        }
+        self.enter()
+    }
+
+    // the terminal may have been resized or written to while it was not
+    // ours
+    fn enter(&mut self) -> io::Result<()> {
+        enter(&mut self.stdout)?;
+        self.size = terminal::size()?;
+        self.shown = None;
+        Ok(())
+    }
+}
+
+impl Drop for UI {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+fn enter(stdout: &mut Stdout) -> io::Result<()> {
+    enable_raw_mode()?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(
+        stdout,
+        SetBackgroundColor(Color::Black),
+        Clear(ClearType::All),
+        cursor::Hide,
+    )
+}
+
+// does nothing unless the terminal is set up, so it is safe to call twice
+fn leave(stdout: &mut Stdout) -> io::Result<()> {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let reset = execute!(
+        stdout,
+        ResetColor,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        cursor::Show
+    );
+    disable_raw_mode().and(reset)
+}
+
+/// The columns and rows an arena of `width` x `height` cells takes up on
+/// screen together with its HUD.
+pub fn screen_size(width: u16, height: u16, cell_width: CellWidth) -> (u16, u16) {
+    (width.saturating_mul(cell_width.columns()), height.saturating_add(HUD_ROWS))
+}
+
+/// Where the top left corner of something `size` big goes to centre it on a
+/// terminal of `terminal` size, or `None` if it does not fit.
+pub fn centre(size: (u16, u16), terminal: (u16, u16)) -> Option<Point2d<u16>> {
+    let x = terminal.0.checked_sub(size.0)?;
+    let y = terminal.1.checked_sub(size.1)?;
+    Some(Point2d::new(x / 2, y / 2))
+}
+
+/// Fills `frame` with a note asking for a terminal of at least `needed`
+/// size, as much of it as fits.
+pub fn draw_too_small(frame: &mut Frame, needed: (u16, u16)) {
+    let terminal = frame.size();
+    let lines = [
+        "Terminal too small".to_string(),
+        format!("need {}x{}, have {}x{}", needed.0, needed.1, terminal.0, terminal.1),
+    ];
+    let top = terminal.1.saturating_sub(lines.len() as u16) / 2;
+    for (line, text) in (top..terminal.1).zip(lines) {
+        let left = terminal.0.saturating_sub(text.chars().count() as u16) / 2;
+        frame.print_str(left, line, &text);
     }
 }
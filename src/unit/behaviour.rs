@@ -0,0 +1,325 @@
+#![allow(non_snake_case)]
+
+pub mod Behaviour {
+    use std::fmt::{self, Display};
+
+    use rand::{Rng, RngCore};
+
+    use crate::{collision, grid::Grid, pathfinding::FlowField, point::point::Point2d, traits::Position, unit::Player};
+
+    /// What an enemy can see when deciding where to go.
+    pub struct Context<'a> {
+        pub grid: &'a Grid,
+        pub flow_field: Option<&'a FlowField>,
+        pub players: &'a [Player],
+        pub rng: &'a mut dyn RngCore,
+    }
+    impl<'a> Context<'a> {
+        /// The living player closest to `position`, which is who enemies go after.
+        /// Falls back to the first player once nobody is left alive.
+        pub fn nearest_player(&self, position: Point2d<f64>) -> &'a Player {
+            let distance = |player: &&Player| player.position().distance(position);
+            self.players
+                .iter()
+                .filter(|player| player.is_alive())
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                .unwrap_or(&self.players[0])
+        }
+    }
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+
+    /// Decides where an enemy heads each tick, the enemy then moves towards
+    /// that point at its own speed. `Display` must print the behaviour and its
+    /// state in the form `parse_behaviour` reads back, so it survives a save.
+    ///
+    /// The trait is sealed: saves, replays and network games can only bring
+    /// back the behaviours `parse_behaviour` knows, so new ones are added to
+    /// this module and to `parse_behaviour` together.
+    pub trait Behaviour: Display + sealed::Sealed {
+        fn target(&mut self, position: Point2d<f64>, context: &mut Context) -> Point2d<f64>;
+    }
+
+    pub fn parse_behaviour(text: &str) -> Option<Box<dyn Behaviour>> {
+        fn point<T: std::str::FromStr>(word: &str) -> Option<Point2d<T>> {
+            let (x, y) = word.split_once(',')?;
+            Some(Point2d::new(x.parse().ok()?, y.parse().ok()?))
+        }
+
+        let mut words = text.split_whitespace();
+        let behaviour: Box<dyn Behaviour> = match words.next()? {
+            "chase" => Box::new(Chase),
+            "wander" => Box::new(Wander {
+                destination: match words.next() {
+                    Some("-") | None => None,
+                    Some(word) => Some(point(word)?),
+                },
+                previous: match words.next() {
+                    Some(word) => Some(point(word)?),
+                    None => None,
+                },
+            }),
+            "ambush" => Box::new(Ambush::new(words.next()?.parse().ok()?)),
+            "flee" => Box::new(Flee),
+            "patrol" => {
+                let next = words.next()?.parse().ok()?;
+                let route = words.by_ref().map(point).collect::<Option<Vec<_>>>()?;
+                if next >= route.len().max(1) {
+                    return None;
+                }
+                Box::new(Patrol { route, next })
+            }
+            _ => return None,
+        };
+        words.next().is_none().then_some(behaviour)
+    }
+
+    fn cell_center(cell: Point2d<u16>) -> Point2d<f64> {
+        Point2d::new(cell.x.into(), cell.y.into())
+    }
+
+    /// Follows the flow field around walls and closes in directly once next
+    /// to the player, or when there is no path to follow.
+    pub struct Chase;
+    impl Chase {
+        fn chase(position: Point2d<f64>, context: &Context) -> Point2d<f64> {
+            context
+                .flow_field
+                .zip(context.grid.cell_at(position))
+                .filter(|(field, cell)| field.distance(*cell).is_some_and(|distance| distance > 1))
+                .and_then(|(field, cell)| field.next_cell(context.grid, cell))
+                .map(cell_center)
+                .unwrap_or_else(|| context.nearest_player(position).position())
+        }
+    }
+    impl sealed::Sealed for Chase {}
+    impl Behaviour for Chase {
+        fn target(&mut self, position: Point2d<f64>, context: &mut Context) -> Point2d<f64> {
+            Self::chase(position, context)
+        }
+    }
+    impl Display for Chase {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "chase")
+        }
+    }
+
+    /// Drifts between random free neighbouring cells.
+    #[derive(Default)]
+    pub struct Wander {
+        destination: Option<Point2d<f64>>,
+        // where the last tick started, to notice the enemy getting nowhere
+        previous: Option<Point2d<f64>>,
+    }
+    impl sealed::Sealed for Wander {}
+    impl Behaviour for Wander {
+        fn target(&mut self, position: Point2d<f64>, context: &mut Context) -> Point2d<f64> {
+            let arrived = self
+                .destination
+                .is_none_or(|destination| position.round() == destination);
+            let stuck = self.previous == Some(position);
+            self.previous = Some(position);
+            if arrived || stuck {
+                self.destination = context.grid.cell_at(position).and_then(|cell| {
+                    let free: Vec<_> = context
+                        .grid
+                        .neighbours(cell)
+                        .filter(|&neighbour| FlowField::passable(context.grid, cell, neighbour))
+                        .collect();
+                    (!free.is_empty()).then(|| cell_center(free[context.rng.gen_range(0..free.len())]))
+                });
+            }
+            self.destination.unwrap_or(position)
+        }
+    }
+    impl Display for Wander {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "wander")?;
+            match (self.destination, self.previous) {
+                (Some(destination), _) => write!(f, " {},{}", destination.x, destination.y)?,
+                (None, Some(_)) => write!(f, " -")?,
+                (None, None) => {}
+            }
+            if let Some(previous) = self.previous {
+                write!(f, " {},{}", previous.x, previous.y)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Walks a closed route of waypoints in order, sliding along any walls between them.
+    pub struct Patrol {
+        route: Vec<Point2d<u16>>,
+        next: usize,
+    }
+    impl Patrol {
+        pub fn new(route: Vec<Point2d<u16>>) -> Self {
+            Self { route, next: 0 }
+        }
+    }
+    impl sealed::Sealed for Patrol {}
+    impl Behaviour for Patrol {
+        fn target(&mut self, position: Point2d<f64>, _context: &mut Context) -> Point2d<f64> {
+            if self.route.is_empty() {
+                return position;
+            }
+            if position.to_u16() == self.route[self.next] {
+                self.next = (self.next + 1) % self.route.len();
+            }
+            cell_center(self.route[self.next])
+        }
+    }
+    impl Display for Patrol {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "patrol {}", self.next)?;
+            for point in &self.route {
+                write!(f, " {},{}", point.x, point.y)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Heads for where the nearest player will be `lookahead` cells ahead on
+    /// their current heading, chasing normally along the flow field when that
+    /// spot is inside a wall or cannot be walked to in a straight line.
+    pub struct Ambush {
+        lookahead: f64,
+    }
+    impl Ambush {
+        pub fn new(lookahead: f64) -> Self {
+            Self { lookahead }
+        }
+    }
+    impl sealed::Sealed for Ambush {}
+    impl Behaviour for Ambush {
+        fn target(&mut self, position: Point2d<f64>, context: &mut Context) -> Point2d<f64> {
+            let player = context.nearest_player(position);
+            let predicted = player.position() + player.direction() * self.lookahead;
+            if collision::resolve(context.grid, position, predicted) == predicted {
+                predicted
+            } else {
+                Chase::chase(position, context)
+            }
+        }
+    }
+    impl Display for Ambush {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "ambush {}", self.lookahead)
+        }
+    }
+
+    /// Runs to whichever neighbouring cell is furthest from the players by path,
+    /// or straight away from the nearest player when there is no path.
+    pub struct Flee;
+    impl sealed::Sealed for Flee {}
+    impl Behaviour for Flee {
+        fn target(&mut self, position: Point2d<f64>, context: &mut Context) -> Point2d<f64> {
+            let player = context.nearest_player(position).position();
+            let away = position + (position - player);
+            let (Some(field), Some(cell)) = (context.flow_field, context.grid.cell_at(position)) else {
+                return away;
+            };
+            let Some(distance) = field.distance(cell) else {
+                return away;
+            };
+            context
+                .grid
+                .neighbours(cell)
+                .filter_map(|neighbour| Some((neighbour, field.distance(neighbour)?)))
+                .filter(|&(_, d)| d > distance)
+                .max_by_key(|&(_, d)| d)
+                .map(|(neighbour, _)| cell_center(neighbour))
+                .unwrap_or(position)
+        }
+    }
+    impl Display for Flee {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "flee")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::{
+        grid::Grid,
+        pathfinding::FlowField,
+        point::point::Point2d,
+        traits::Position,
+        unit::{parse_behaviour, Context, Enemy, Player, PlayerBuilder},
+    };
+
+    #[test]
+    fn behaviours_round_trip_through_their_text() {
+        let texts = [
+            "chase",
+            "wander",
+            "wander 3,4",
+            "wander - 2.5,4",
+            "wander 3,4 2.5,4",
+            "patrol 1 2,3 4,5",
+            "ambush 3",
+            "flee",
+        ];
+        for text in texts {
+            assert_eq!(parse_behaviour(text).unwrap().to_string(), text);
+        }
+        assert!(parse_behaviour("wander - 2.5,4 1,1").is_none());
+    }
+
+    #[test]
+    fn wanderers_do_not_get_stuck_between_diagonal_walls() {
+        let mut grid = Grid::new(12, 12);
+        // touching at the corner of the wanderer's south east neighbour
+        grid.block(Point2d::new(6, 5));
+        grid.block(Point2d::new(5, 6));
+        let players = [Player::default()];
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut enemy = Enemy::wandering(0.5);
+            enemy.set_position(Point2d::new(5.0, 5.0));
+            let mut still = 0;
+            for _ in 0..50 {
+                let start = enemy.position();
+                let mut context = Context {
+                    grid: &grid,
+                    flow_field: None,
+                    players: &players,
+                    rng: &mut rng,
+                };
+                enemy.update(&mut context);
+                still = if enemy.position() == start { still + 1 } else { 0 };
+                assert!(still < 2, "seed {} stuck at {:?}", seed, enemy.position());
+            }
+        }
+    }
+
+    #[test]
+    fn ambushers_go_around_walls_between_them_and_the_player() {
+        let mut grid = Grid::new(20, 20);
+        // the player is heading along the far side of a wall with one gap at the east end
+        for x in 0..17 {
+            grid.block(Point2d::new(x, 10));
+        }
+        let players = [PlayerBuilder::new().position(5.5, 15.5).direction(1.0, 0.0).build()];
+        let flow_field = FlowField::new(&grid, players[0].position().to_u16());
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut enemy = Enemy::ambushing(0.5, 3.0);
+        enemy.set_position(Point2d::new(5.5, 5.5));
+        for _ in 0..200 {
+            let mut context = Context {
+                grid: &grid,
+                flow_field: Some(&flow_field),
+                players: &players,
+                rng: &mut rng,
+            };
+            enemy.update(&mut context);
+        }
+        assert!(enemy.position().distance(players[0].position()) <= 3.0, "stuck at {:?}", enemy.position());
+    }
+}
@@ -1,17 +1,81 @@
 #![allow(non_snake_case)]
 
 pub mod Collectible {
-    use crate::{traits::Position, point::point::Point2d};
+    use std::{fmt, str::FromStr};
+
+    use crate::{traits::Position, point::point::Point2d, unit::Effect};
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    pub enum CollectibleKind {
+        /// Worth a point, there is always exactly one on the field.
+        Heart,
+        Health,
+        SpeedBoost,
+        Shield,
+        ScoreMultiplier,
+        Magnet,
+    }
+    impl CollectibleKind {
+        pub const POWER_UPS: [CollectibleKind; 5] = [
+            CollectibleKind::Health,
+            CollectibleKind::SpeedBoost,
+            CollectibleKind::Shield,
+            CollectibleKind::ScoreMultiplier,
+            CollectibleKind::Magnet,
+        ];
+        /// The timed effect picking this up grants, if any.
+        pub fn effect(&self) -> Option<Effect> {
+            match self {
+                CollectibleKind::Heart | CollectibleKind::Health => None,
+                CollectibleKind::SpeedBoost => Some(Effect::SpeedBoost),
+                CollectibleKind::Shield => Some(Effect::Shield),
+                CollectibleKind::ScoreMultiplier => Some(Effect::ScoreMultiplier),
+                CollectibleKind::Magnet => Some(Effect::Magnet),
+            }
+        }
+        pub fn name(&self) -> &'static str {
+            match self {
+                CollectibleKind::Heart => "heart",
+                CollectibleKind::Health => "health",
+                CollectibleKind::SpeedBoost => "speed",
+                CollectibleKind::Shield => "shield",
+                CollectibleKind::ScoreMultiplier => "multiplier",
+                CollectibleKind::Magnet => "magnet",
+            }
+        }
+    }
+    impl fmt::Display for CollectibleKind {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+    impl FromStr for CollectibleKind {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            std::iter::once(CollectibleKind::Heart)
+                .chain(CollectibleKind::POWER_UPS)
+                .find(|kind| kind.name() == s)
+                .ok_or_else(|| format!("unknown collectible `{}`", s))
+        }
+    }
 
     pub struct Collectible {
         position: Point2d<u16>,
+        kind: CollectibleKind,
     }
     impl Collectible {
         pub fn new(x: u16, y: u16) -> Collectible {
+            Self::with_kind(x, y, CollectibleKind::Heart)
+        }
+        pub fn with_kind(x: u16, y: u16, kind: CollectibleKind) -> Collectible {
             Self {
                 position: Point2d::new(x, y),
+                kind,
             }
         }
+        pub fn kind(&self) -> CollectibleKind {
+            self.kind
+        }
     }
     impl Default for Collectible {
         fn default() -> Self {
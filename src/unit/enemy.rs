@@ -1,32 +1,87 @@
 #![allow(non_snake_case)]
 
 pub mod Enemy {
-    use crate::{point::point::Point2d, traits::Position, unit::Player};
+    use crate::{
+        collision,
+        grid::Grid,
+        point::point::Point2d,
+        traits::Position,
+        unit::{Ambush, Behaviour, Chase, Context, Flee, Patrol, Wander},
+    };
     
     pub struct Enemy {
         position: Point2d<f64>,
         speed: f64,
+        health: u8,
+        behaviour: Box<dyn Behaviour>,
     }
     impl Enemy {
-        pub fn with_speed(speed: f64) -> Self {
+        pub fn with_behaviour(speed: f64, behaviour: Box<dyn Behaviour>) -> Self {
             Self {
                 position: Point2d::default(),
                 speed,
+                health: 1,
+                behaviour,
             }
         }
-        pub fn move_towards_player(&mut self, player: &Player) {
-            let change = player.position() - self.position();
-            let length = (f64::powi(change.x, 2)+f64::powi(change.y, 2)).sqrt();
-            self.position.x += self.speed * change.x / length;
-            self.position.y += self.speed * change.y / length;
+        pub fn with_health(mut self, health: u8) -> Self {
+            self.health = health;
+            self
+        }
+        pub fn with_speed(speed: f64) -> Self {
+            Self::chasing(speed)
+        }
+        pub fn chasing(speed: f64) -> Self {
+            Self::with_behaviour(speed, Box::new(Chase))
+        }
+        pub fn wandering(speed: f64) -> Self {
+            Self::with_behaviour(speed, Box::<Wander>::default())
+        }
+        pub fn patrolling(speed: f64, route: Vec<Point2d<u16>>) -> Self {
+            Self::with_behaviour(speed, Box::new(Patrol::new(route)))
+        }
+        pub fn ambushing(speed: f64, lookahead: f64) -> Self {
+            Self::with_behaviour(speed, Box::new(Ambush::new(lookahead)))
+        }
+        pub fn fleeing(speed: f64) -> Self {
+            Self::with_behaviour(speed, Box::new(Flee))
+        }
+        pub fn speed(&self) -> f64 {
+            self.speed
+        }
+        pub(crate) fn set_speed(&mut self, speed: f64) {
+            self.speed = speed;
+        }
+        pub fn health(&self) -> u8 {
+            self.health
+        }
+        pub fn is_alive(&self) -> bool {
+            self.health > 0
+        }
+        pub fn take_damage(&mut self, damage: u8) {
+            self.health = self.health.saturating_sub(damage);
+        }
+        pub fn behaviour(&self) -> &dyn Behaviour {
+            self.behaviour.as_ref()
+        }
+        pub fn update(&mut self, context: &mut Context) {
+            let target = self.behaviour.target(self.position, context);
+            self.move_towards(target, context.grid);
+        }
+        fn move_towards(&mut self, target: Point2d<f64>, grid: &Grid) {
+            let offset = target - self.position;
+            // already there, there is no direction to move in
+            let Some(direction) = offset.normalize() else {
+                return;
+            };
+            // stop on the target rather than overshooting it
+            let next = self.position + direction * self.speed.min(offset.length());
+            self.position = collision::resolve(grid, self.position, next);
         }
     }
     impl Default for Enemy {
         fn default() -> Self {
-            Self {
-                position: Point2d::default(),
-                speed: 0.0,
-            }
+            Self::with_speed(0.0)
         }
     }
     impl Position<f64> for Enemy {
@@ -38,3 +93,37 @@ pub mod Enemy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::Enemy::Enemy;
+    use crate::{grid::Grid, point::point::Point2d, traits::Position, unit::Context, unit::Player};
+
+    #[test]
+    fn fast_patrols_reach_every_waypoint() {
+        let grid = Grid::new(40, 20);
+        let players = [Player::default()];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        // steps of 1.5 from x=10 land on 19 and 20.5, both a cell off x=20
+        let route = vec![Point2d::new(20, 10), Point2d::new(10, 10)];
+        let mut enemy = Enemy::patrolling(1.5, route.clone());
+        enemy.set_position(Point2d::new(10.0, 10.0));
+        let mut visited = vec![];
+        for _ in 0..40 {
+            let mut context = Context {
+                grid: &grid,
+                flow_field: None,
+                players: &players,
+                rng: &mut rng,
+            };
+            enemy.update(&mut context);
+            if route.contains(&enemy.position().to_u16()) && visited.last() != Some(&enemy.position().to_u16()) {
+                visited.push(enemy.position().to_u16());
+            }
+        }
+        assert_eq!(&visited[..4], &[route[0], route[1], route[0], route[1]]);
+    }
+}
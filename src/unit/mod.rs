@@ -6,3 +6,7 @@ pub mod player;
 pub use player::Player::*;
 pub mod collectible;
 pub use collectible::Collectible::*;
+pub mod behaviour;
+pub use behaviour::Behaviour::*;
+pub mod projectile;
+pub use projectile::Projectile::*;
@@ -1,27 +1,201 @@
 #![allow(non_snake_case)]
 
 pub mod Player {
-    use std::fmt::{self, Display};
-    use crate::{traits::Position, point::point::Point2d};
-    
+    use std::{fmt::{self, Display}, str::FromStr};
+    use crate::{traits::Position, point::point::{Point2d, Vec2}};
+
+    // how much faster the speed power-up makes the player
+    const SPEED_BOOST: f64 = 1.5;
+
+    #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+    pub enum Effect {
+        SpeedBoost,
+        Shield,
+        ScoreMultiplier,
+        Magnet,
+    }
+    impl Effect {
+        const ALL: [Effect; 4] = [Effect::SpeedBoost, Effect::Shield, Effect::ScoreMultiplier, Effect::Magnet];
+        pub fn name(&self) -> &'static str {
+            match self {
+                Effect::SpeedBoost => "speed",
+                Effect::Shield => "shield",
+                Effect::ScoreMultiplier => "multiplier",
+                Effect::Magnet => "magnet",
+            }
+        }
+    }
+    impl Display for Effect {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+    impl FromStr for Effect {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Effect::ALL
+                .into_iter()
+                .find(|effect| effect.name() == s)
+                .ok_or_else(|| format!("unknown effect `{}`", s))
+        }
+    }
+
+    /// How finely the player can aim.
+    #[derive(PartialEq, Clone, Copy, Debug)]
+    pub enum Steering {
+        /// Turning moves between this many evenly spaced headings.
+        Steps(u16),
+        /// Turning rotates the heading by this many degrees, any heading is possible.
+        Free(f64),
+    }
+    impl Steering {
+        /// The heading in degrees after turning `turns` times from `heading`,
+        /// negative turns go left.
+        pub fn turn(&self, heading: f64, turns: i8) -> f64 {
+            let heading = match *self {
+                Steering::Steps(steps) => {
+                    let step = 360.0 / f64::from(steps.max(1));
+                    ((heading / step).round() + f64::from(turns)) * step
+                }
+                Steering::Free(degrees) => heading + degrees * f64::from(turns),
+            };
+            heading.rem_euclid(360.0)
+        }
+    }
+    impl Default for Steering {
+        fn default() -> Self {
+            Steering::Steps(8)
+        }
+    }
+    impl Display for Steering {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Steering::Steps(steps) => write!(f, "steps:{}", steps),
+                Steering::Free(degrees) => write!(f, "free:{}", degrees),
+            }
+        }
+    }
+    impl FromStr for Steering {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let steering = match s.split_once(':') {
+                Some(("steps", steps)) => steps.parse().ok().filter(|&steps| steps > 0).map(Steering::Steps),
+                // a turn of NaN or infinite degrees would leave no heading at all
+                Some(("free", degrees)) => degrees
+                    .parse()
+                    .ok()
+                    .filter(|degrees: &f64| degrees.is_finite())
+                    .map(Steering::Free),
+                _ => None,
+            };
+            steering.ok_or_else(|| format!("unknown steering `{}`, expected steps:<count> or free:<degrees>", s))
+        }
+    }
+
+    /// How the player's speed turns into movement.
+    #[derive(PartialEq, Clone, Copy, Debug, Default)]
+    pub enum Movement {
+        /// The player moves at their speed straight along their heading,
+        /// turning redirects them at once.
+        #[default]
+        Direct,
+        /// Speed works as a throttle: each tick friction takes its share of
+        /// the velocity, the throttle pushes it along the heading by
+        /// `acceleration` times the throttle and the result is capped at
+        /// `max_speed`. Turning swings the heading while the player keeps
+        /// drifting the old way.
+        Inertia { acceleration: f64, friction: f64, max_speed: f64 },
+    }
+    impl Movement {
+        /// Inertia tuned so that a held throttle settles at the speed it
+        /// would move at directly.
+        pub fn inertia() -> Self {
+            Movement::Inertia { acceleration: 0.1, friction: 0.1, max_speed: 1.0 }
+        }
+    }
+    impl Display for Movement {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Movement::Direct => write!(f, "direct"),
+                Movement::Inertia { acceleration, friction, max_speed } => {
+                    write!(f, "inertia:{},{},{}", acceleration, friction, max_speed)
+                }
+            }
+        }
+    }
+    impl FromStr for Movement {
+        type Err = String;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let movement = match s.split_once(':') {
+                None if s == "direct" => Some(Movement::Direct),
+                None if s == "inertia" => Some(Movement::inertia()),
+                Some(("inertia", parameters)) => {
+                    // one bad or infinite field fails the whole movement
+                    let parameters: Option<Vec<f64>> = parameters
+                        .split(',')
+                        .map(|p| p.parse().ok().filter(|p: &f64| p.is_finite()))
+                        .collect();
+                    match parameters.as_deref().unwrap_or_default() {
+                        &[acceleration, friction, max_speed]
+                            if acceleration >= 0.0 && (0.0..=1.0).contains(&friction) && max_speed >= 0.0 =>
+                        {
+                            Some(Movement::Inertia { acceleration, friction, max_speed })
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            movement.ok_or_else(|| {
+                format!("unknown movement `{}`, expected direct, inertia or inertia:<acceleration>,<friction>,<max speed>", s)
+            })
+        }
+    }
+
     pub struct Player {
         position: Point2d<f64>,
+        // in degrees, clockwise from facing right
+        heading: f64,
+        steering: Steering,
         speed: f64,
+        movement: Movement,
+        // only kept up under inertia, direct movement derives it from the heading
+        velocity: Vec2,
         health: u8,
         noclip: bool,
         speedlimit: bool,
         invincibility: bool,
+        // ticks of invulnerability granted by each hit, and how many are left
+        invulnerability: u16,
+        invulnerable_ticks: u16,
+        reload_ticks: u16,
+        max_health: u8,
+        // timed power-up effects and the ticks they have left
+        effects: Vec<(Effect, u16)>,
+        // tells the players apart, drawn next to the face and in wide cells
+        weapon: char,
     }
     impl Player {
         pub fn builder() -> PlayerBuilder {
             PlayerBuilder::default()
         }
+        pub fn weapon(&self) -> char {
+            self.weapon
+        }
+        /// A face that gets more worried the less health is left, calm from
+        /// 10 health up.
+        pub fn face(&self) -> char {
+            const FACES: [char; 10] = ['😭', '😱', '😨', '😫', '😩', '😧', '😖', '😞', '😑', '😐'];
+            FACES[usize::from(self.health.saturating_sub(1)).min(FACES.len() - 1)]
+        }
         pub fn is_alive(&self) -> bool {
             self.health > 0
         }
-        pub fn take_damage(&mut self, damage: u8) {
-            if self.invincibility {
-                return;
+        /// Returns whether the hit landed, hits taken while invincible or
+        /// still recovering from the previous hit are ignored.
+        pub fn take_damage(&mut self, damage: u8) -> bool {
+            if self.invincibility || self.invulnerable_ticks > 0 || self.has_effect(Effect::Shield) {
+                return false;
             }
             if self.health >= damage {
                 self.health -= damage;
@@ -29,6 +203,66 @@ pub mod Player {
             else {
                 self.health = 0;
             }
+            self.invulnerable_ticks = self.invulnerability;
+            true
+        }
+        pub fn is_invulnerable(&self) -> bool {
+            self.invulnerable_ticks > 0
+        }
+        pub fn invulnerable_ticks(&self) -> u16 {
+            self.invulnerable_ticks
+        }
+        pub(crate) fn set_invulnerable_ticks(&mut self, ticks: u16) {
+            self.invulnerable_ticks = ticks;
+        }
+        pub fn invulnerability(&self) -> u16 {
+            self.invulnerability
+        }
+        /// Counts down the invulnerability left from the last hit, the reload
+        /// of the last shot and every timed effect, once per tick.
+        pub fn recover(&mut self) {
+            self.invulnerable_ticks = self.invulnerable_ticks.saturating_sub(1);
+            self.reload_ticks = self.reload_ticks.saturating_sub(1);
+            self.effects.iter_mut().for_each(|(_, ticks)| *ticks -= 1);
+            self.effects.retain(|&(_, ticks)| ticks > 0);
+        }
+        pub fn heal(&mut self, health: u8) {
+            self.health = self.health.saturating_add(health).min(self.max_health.max(self.health));
+        }
+        pub fn max_health(&self) -> u8 {
+            self.max_health
+        }
+        pub(crate) fn set_health(&mut self, health: u8, max_health: u8) {
+            self.health = health;
+            self.max_health = max_health;
+        }
+        /// Starts `effect` for `ticks`, or extends it if it is already running.
+        pub fn add_effect(&mut self, effect: Effect, ticks: u16) {
+            match self.effects.iter_mut().find(|(active, _)| *active == effect) {
+                Some((_, remaining)) => *remaining = (*remaining).max(ticks),
+                None if ticks > 0 => self.effects.push((effect, ticks)),
+                None => {}
+            }
+        }
+        pub fn has_effect(&self, effect: Effect) -> bool {
+            self.effects.iter().any(|(active, _)| *active == effect)
+        }
+        pub fn effects(&self) -> &[(Effect, u16)] {
+            &self.effects
+        }
+        /// Starts reloading for `reload` ticks and returns true if the gun was ready.
+        pub fn fire(&mut self, reload: u16) -> bool {
+            if self.reload_ticks > 0 {
+                return false;
+            }
+            self.reload_ticks = reload;
+            true
+        }
+        pub fn reload_ticks(&self) -> u16 {
+            self.reload_ticks
+        }
+        pub(crate) fn set_reload_ticks(&mut self, ticks: u16) {
+            self.reload_ticks = ticks;
         }
         pub fn health(&self) -> u8 {
             self.health
@@ -52,17 +286,75 @@ pub mod Player {
                 self.speed = 0.0;
             }
         }
+        fn effective_speed(&self) -> f64 {
+            if self.has_effect(Effect::SpeedBoost) {
+                self.speed * SPEED_BOOST
+            } else {
+                self.speed
+            }
+        }
+        pub fn movement(&self) -> Movement {
+            self.movement
+        }
+        /// How far the player moves this tick.
+        pub fn velocity(&self) -> Vec2 {
+            match self.movement {
+                Movement::Direct => self.direction() * self.effective_speed(),
+                Movement::Inertia { .. } => self.velocity,
+            }
+        }
+        pub(crate) fn set_velocity(&mut self, velocity: Vec2) {
+            self.velocity = velocity;
+        }
+        /// Applies one tick of thrust and friction to the velocity, does
+        /// nothing under direct movement.
+        pub fn drive(&mut self) {
+            let Movement::Inertia { acceleration, friction, max_speed } = self.movement else {
+                return;
+            };
+            let max_speed = if self.has_effect(Effect::SpeedBoost) {
+                max_speed * SPEED_BOOST
+            } else {
+                max_speed
+            };
+            let thrust = self.direction() * (self.effective_speed() * acceleration);
+            let velocity = self.velocity * (1.0 - friction) + thrust;
+            self.velocity = match velocity.normalize() {
+                Some(heading) if velocity.length() > max_speed => heading * max_speed,
+                _ => velocity,
+            };
+        }
+        /// Keeps only the part of the velocity the player actually moved,
+        /// so running into a wall stops the drift into it.
+        pub fn collide(&mut self, moved: Vec2) {
+            if let Movement::Inertia { .. } = self.movement {
+                self.velocity = moved;
+            }
+        }
         pub fn move_forward(&mut self) {
-            self.position.move_forward(self.speed);
+            self.position += self.velocity();
+        }
+        pub fn next_position(&self) -> Point2d<f64> {
+            self.position + self.velocity()
+        }
+        /// The unit vector the player faces.
+        pub fn direction(&self) -> Vec2 {
+            Vec2::from_angle(self.heading.to_radians())
+        }
+        pub fn heading(&self) -> f64 {
+            self.heading
         }
-        pub fn forward_position(&self) -> Point2d<u16> {
-            self.position.simulate_move_forward(self.speed).to_u16()
+        pub(crate) fn set_heading(&mut self, heading: f64) {
+            self.heading = heading.rem_euclid(360.0);
+        }
+        pub fn steering(&self) -> Steering {
+            self.steering
         }
         pub fn turn_left(&mut self) {
-            self.position.update_direction(-1);
+            self.heading = self.steering.turn(self.heading, -1);
         }
         pub fn turn_right(&mut self) {
-            self.position.update_direction(1);
+            self.heading = self.steering.turn(self.heading, 1);
         }
         pub fn toggle_noclip(&mut self) {
             self.noclip ^= true; 
@@ -73,6 +365,12 @@ pub mod Player {
         pub fn noclip(&self) -> bool {
             self.noclip
         }
+        pub fn speed_limit(&self) -> bool {
+            self.speedlimit
+        }
+        pub fn invincibility(&self) -> bool {
+            self.invincibility
+        }
         pub fn unlimited_health(&mut self) {
             self.health = u8::MAX;
         }
@@ -84,20 +382,24 @@ pub mod Player {
         fn default() -> Self {
             Self {
                 position: Point2d::new(30.0, 15.0),
+                heading: 0.0,
+                steering: Steering::default(),
                 speed: 0.0,
+                movement: Movement::default(),
+                velocity: Vec2::ZERO,
                 health: 10,
                 noclip: false,
                 speedlimit: true,
                 invincibility: false,
+                invulnerability: 0,
+                invulnerable_ticks: 0,
+                reload_ticks: 0,
+                max_health: 10,
+                effects: vec![],
+                weapon: '🔫',
             }
         }
     }
-    impl Display for Player {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let expression = "😭😱😨😫😩😧😖😞😑😐".chars().nth(self.health as usize - 1).unwrap_or_default();
-            write!(f, "🔫{}", expression)
-        }
-    }
     impl Position<f64> for Player {
         fn position(&self) -> Point2d<f64> {
             self.position
@@ -107,10 +409,17 @@ pub mod Player {
         }
     }
 
+    #[derive(Clone)]
     pub struct PlayerBuilder {
         position: Point2d<f64>,
+        heading: f64,
+        steering: Steering,
         speed: f64,
+        movement: Movement,
         health: u8,
+        max_health: Option<u8>,
+        invulnerability: u16,
+        weapon: char,
     }
     impl PlayerBuilder {
         pub fn new() -> PlayerBuilder {
@@ -124,19 +433,59 @@ pub mod Player {
             self.health = health;
             self
         }
+        /// The most health can be healed back up to, the starting health by default.
+        pub fn max_health(mut self, max_health: u8) -> PlayerBuilder {
+            self.max_health = Some(max_health);
+            self
+        }
+        pub fn invulnerability(mut self, ticks: u16) -> PlayerBuilder {
+            self.invulnerability = ticks;
+            self
+        }
+        pub fn weapon(mut self, weapon: char) -> PlayerBuilder {
+            self.weapon = weapon;
+            self
+        }
+        pub fn position(mut self, x: f64, y: f64) -> PlayerBuilder {
+            self.position.x = x;
+            self.position.y = y;
+            self
+        }
+        /// The starting heading in degrees, clockwise from facing right.
+        pub fn heading(mut self, heading: f64) -> PlayerBuilder {
+            self.heading = heading.rem_euclid(360.0);
+            self
+        }
         pub fn direction(mut self, x: f64, y: f64) -> PlayerBuilder {
-            self.position.direction.0 = x;
-            self.position.direction.1 = y;
+            self.heading = Vec2::new(x, y).angle().to_degrees().rem_euclid(360.0);
+            self
+        }
+        pub fn steering(mut self, steering: Steering) -> PlayerBuilder {
+            self.steering = steering;
+            self
+        }
+        pub fn movement(mut self, movement: Movement) -> PlayerBuilder {
+            self.movement = movement;
             self
         }
         pub fn build(self) -> Player {
             Player {
                 position: self.position,
+                heading: self.heading,
+                steering: self.steering,
                 speed: self.speed,
+                movement: self.movement,
+                velocity: Vec2::ZERO,
                 health: self.health, 
                 noclip: false,
                 speedlimit: true,
                 invincibility: false,
+                invulnerability: self.invulnerability,
+                invulnerable_ticks: 0,
+                reload_ticks: 0,
+                max_health: self.max_health.unwrap_or(self.health),
+                effects: vec![],
+                weapon: self.weapon,
             }
         }
     }
@@ -144,9 +493,49 @@ pub mod Player {
         fn default() -> Self {
             Self {
                 position: Point2d::new(30.0, 15.0),
+                heading: 0.0,
+                steering: Steering::default(),
                 speed: 0.0,
+                movement: Movement::default(),
                 health: 10,
+                max_health: None,
+                invulnerability: 10,
+                weapon: '🔫',
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Player::{Movement, PlayerBuilder, Steering};
+
+    #[test]
+    fn free_steering_needs_a_finite_turn() {
+        assert_eq!("free:7.5".parse(), Ok(Steering::Free(7.5)));
+        for text in ["free:nan", "free:NaN", "free:inf", "free:-inf", "free:infinity"] {
+            assert!(text.parse::<Steering>().is_err(), "{} accepted", text);
+        }
+    }
+
+    #[test]
+    fn inertia_needs_every_field_to_be_a_number() {
+        let parsed = "inertia:0.1,0.5,1".parse();
+        assert_eq!(parsed, Ok(Movement::Inertia { acceleration: 0.1, friction: 0.5, max_speed: 1.0 }));
+        for text in ["inertia:0.1,x,0.5,1", "inertia:0.1,0.5,1,", "inertia:0.1,,0.5", "inertia:inf,0.5,1"] {
+            assert!(text.parse::<Movement>().is_err(), "{} accepted", text);
+        }
+    }
+
+    #[test]
+    fn hits_while_recovering_are_ignored() {
+        let mut player = PlayerBuilder::new().health(10).invulnerability(2).build();
+        assert!(player.take_damage(3));
+        assert!(!player.take_damage(3));
+        player.recover();
+        assert!(!player.take_damage(3));
+        player.recover();
+        assert!(player.take_damage(3));
+        assert_eq!(player.health(), 4);
+    }
+}
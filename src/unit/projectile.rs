@@ -0,0 +1,53 @@
+#![allow(non_snake_case)]
+
+pub mod Projectile {
+    use crate::{traits::Position, point::point::{Point2d, Vec2}};
+
+    pub struct Projectile {
+        position: Point2d<f64>,
+        direction: Vec2,
+        speed: f64,
+        damage: u8,
+        owner: usize,
+    }
+    impl Projectile {
+        /// Fired from `position` along `direction`, which should be a unit vector.
+        pub fn new(position: Point2d<f64>, direction: Vec2, speed: f64, damage: u8) -> Self {
+            Self {
+                position,
+                direction,
+                speed,
+                damage,
+                owner: 0,
+            }
+        }
+        /// The index of the player who fired it, who gets the points for its kills.
+        pub fn with_owner(mut self, owner: usize) -> Self {
+            self.owner = owner;
+            self
+        }
+        pub fn owner(&self) -> usize {
+            self.owner
+        }
+        pub fn direction(&self) -> Vec2 {
+            self.direction
+        }
+        pub fn speed(&self) -> f64 {
+            self.speed
+        }
+        pub fn damage(&self) -> u8 {
+            self.damage
+        }
+        pub fn move_forward(&mut self) {
+            self.position += self.direction * self.speed;
+        }
+    }
+    impl Position<f64> for Projectile {
+        fn position(&self) -> Point2d<f64> {
+            self.position
+        }
+        fn set_position(&mut self, position: Point2d<f64>) {
+            self.position = position;
+        }
+    }
+}